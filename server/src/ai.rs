@@ -142,10 +142,12 @@ impl Ai {
             Wenn der Name der Zutat vage ist, z. B. „Curry“, verwende die angegebene Menge, um zu bestimmen, was gemeint ist. Für 1 TL Curry wäre z. B. der beste Suchbegriff „Currypulver“, nicht nur „Curry“, da letzteres zu vage ist und Ergebnisse wie Currypaste liefern könnte.
             Falls dieselbe Zutat mehrfach erwähnt wird, z. B. für Teig und Sauce, dann liste sie nur einmal und addiere die Mengen.
 
-            Für "unit" sind einzig und allein diese werte zulässig: "Gramm", "Kilogramm", "Milliliter", "Liter", "Stück".
+            Für "unit" sind einzig und allein diese werte zulässig: "g", "kg", "ml", "l", "EL", "TL", "Prise", "Bund", "Dose", "Stück".
             "quantity" gibt die Menge der Zutat in der Einheit an. Wenn möglich als Ganzzahl, ansonsten als Dezimalzahl.
             Rechne "unit" und "quantity" entsprechend um, fall die im Rezept angegebene einheit nicht in der liste der zulässigen einheiten ist.
             
+            "source_line" ist die Zeile des Rezepts, in der die Zutat genannt wird, unverändert übernommen.
+
             Wenn die Zutat sehr wahrscheinlich in einem normalen Haushalt vorhanden ist, setze "probably_at_home" auf „true“.
             Beispiele dafür sind Pfeffer, Salz, Zucker, Wasser, Eiswürfel usw.
 
@@ -154,8 +156,9 @@ impl Ai {
             [
                 {
                     "name": "Olivenöl",
-                    "unit": "milliliter",
+                    "unit": "ml",
                     "quantity": 50,
+                    "source_line": "50 ml extra natives Olivenöl",
                     "probably_at_home": true
                 },
                 ...
@@ -165,7 +168,9 @@ impl Ai {
         let prompt = format!("{prompt}\n\nRezept: {recipe}");
         let response = self.ask(db, username, &prompt).await?;
         let mut ingredients = serde_json::from_str::<Vec<Ingredient>>(&response)?;
-        ingredients.iter_mut().for_each(|i| i.enrich());
+        for ingredient in ingredients.iter_mut() {
+            ingredient.enrich(db).await?;
+        }
         Ok(ingredients)
     }
 
@@ -174,7 +179,8 @@ impl Ai {
         db: &Surreal<Any>,
         username: &String,
         ingredient: &mut Ingredient,
-    ) -> Result<(), Error> {
+        prefer_offers: bool,
+    ) -> Result<bool, Error> {
         // check if item list is empty
 
         if ingredient.alternatives.is_empty() {
@@ -201,8 +207,13 @@ impl Ai {
                 "pieces_required": 1
             }
         "#;
+        let offers = if prefer_offers {
+            "Wenn mehrere Artikel gleich gut passen, bevorzuge Artikel im Angebot (offer), deren Angebot nicht loyalty_only ist."
+        } else {
+            ""
+        };
         let prompt = format!(
-            "{prompt}\n\nZutat: {}\n\nBenötigte Menge der Zutat: {} {}\n\nArtikel aus dem Supermakrt: {:?}",
+            "{prompt}\n\n{offers}\n\nZutat: {}\n\nBenötigte Menge der Zutat: {} {}\n\nArtikel aus dem Supermakrt: {:?}",
             ingredient.name,
             ingredient.quantity,
             ingredient.unit,
@@ -217,23 +228,122 @@ impl Ai {
         // ckeck if ai found a match
 
         let Some(index) = response.item_index else {
-            error!("ai refused to select item for ingredient: {ingredient:?}");
-            return Err(Error::InternalServer);
+            warn!(
+                "ai refused to select item for ingredient: {}",
+                ingredient.name
+            );
+            return Ok(false);
         };
 
         // check if index of match is in range
 
         let Some(item) = ingredient.alternatives.get(index).cloned() else {
             error!("ai selected item index out of range");
-            return Ok(());
+            return Ok(false);
         };
 
         // set item
 
         ingredient.select_item(item.id, Some(response.pieces_required));
 
-        Ok(())
+        Ok(true)
+    }
+
+    pub async fn suggest_search_terms(
+        &self,
+        db: &Surreal<Any>,
+        username: &String,
+        ingredient: &Ingredient,
+    ) -> Result<SearchTermSuggestion, Error> {
+        let prompt = r#"
+            Die Suche nach einer Zutat in der API eines Supermarktes hat keine passenden Artikel geliefert.
+            Schlage alternative Suchbegriffe für die Zutat vor, z. B. Synonyme, gängigere Bezeichnungen oder die Einzahl bzw. Mehrzahl.
+            Sortiere die Suchbegriffe absteigend nach der Wahrscheinlichkeit, dass sie einen passenden Artikel liefern.
+            Gib außerdem eine breitere Kategorie an, in der die Zutat zu finden ist, z. B. „Blattsalat“ für „Lollo Rosso“.
+
+            Antwort im folgenden Format, damit die Antwort geparst werden kann. Verzichte auf backticks oder andere formatierung.
+
+            {
+                "alternatives": ["Frühlingszwiebel", "Lauchzwiebel"],
+                "category": "Zwiebel"
+            }
+        "#;
+        let prompt = format!(
+            "{prompt}\n\nZutat: {}\n\nBenötigte Menge der Zutat: {} {}",
+            ingredient.name, ingredient.quantity, ingredient.unit,
+        );
+
+        let response = self.ask(db, username, &prompt).await?;
+        let suggestion = serde_json::from_str::<SearchTermSuggestion>(&response)?;
+
+        Ok(suggestion)
     }
+
+    pub async fn check_diet(
+        &self,
+        db: &Surreal<Any>,
+        username: &String,
+        profile: &DietaryProfile,
+        ingredients: &[Ingredient],
+    ) -> Result<Vec<DietConflict>, Error> {
+        let prompt = r#"
+            Prüfe die Zutaten eines Rezepts gegen das Ernährungsprofil eines Nutzers.
+            Das Profil besteht aus Ernährungsformen (z. B. vegan), Allergenen, die der Nutzer nicht verträgt, und Zutaten, die der Nutzer nicht mag.
+
+            Liste nur Zutaten, die nicht zum Profil passen.
+            "conflicts" enthält die verletzten Ernährungsformen wie im Profil angegeben, "allergen:<Allergen>" für Allergene oder "disliked" für nicht gemochte Zutaten.
+            "substitute" ist ein Ersatz, der zum Profil passt und im Supermarkt erhältlich ist, z. B. „Haferdrink“ für „Milch“ bei veganer Ernährung.
+            Falls es keinen sinnvollen Ersatz gibt, setze "substitute" auf null.
+            "name" muss exakt dem Namen der Zutat entsprechen.
+
+            Antwort im folgenden Format, damit die Antwort geparst werden kann. Verzichte auf backticks oder andere formatierung.
+
+            [
+                {
+                    "name": "Milch",
+                    "conflicts": ["vegan", "lactose_free"],
+                    "substitute": "Haferdrink"
+                },
+                ...
+            ]
+        "#;
+        let diets = profile
+            .diets
+            .iter()
+            .map(|d| {
+                format!(
+                    "{} ({})",
+                    serde_json::to_string(d)
+                        .unwrap_or_default()
+                        .trim_matches('"'),
+                    d.label()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let names = ingredients
+            .iter()
+            .map(|i| i.name.clone())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let prompt = format!(
+            "{prompt}\n\nErnährungsformen: {diets}\n\nAllergene: {}\n\nNicht gemocht: {}\n\nZutaten: {names}",
+            profile.allergens.join(", "),
+            profile.disliked.join(", "),
+        );
+
+        let response = self.ask(db, username, &prompt).await?;
+        let conflicts = serde_json::from_str::<Vec<DietConflict>>(&response)?;
+
+        Ok(conflicts)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct SearchTermSuggestion {
+    #[serde(default)]
+    pub alternatives: Vec<String>,
+    pub category: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Default)]
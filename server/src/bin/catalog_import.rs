@@ -0,0 +1,29 @@
+use dotenv::dotenv;
+use recipe_robot::{model::vendor::catalog::Catalog, setup_db};
+use std::path::PathBuf;
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    tracing_subscriber::fmt::init();
+
+    let mut args = std::env::args().skip(1);
+    let path = PathBuf::from(
+        args.next()
+            .expect("usage: catalog_import <products.csv|products.json> [catalog name]"),
+    );
+    let name = args
+        .next()
+        .or_else(|| dotenv::var("CATALOG_NAME").ok())
+        .unwrap_or_else(|| "default".into());
+
+    let products = Catalog::read_file(&path).expect("fails to read catalog file");
+    let db = setup_db().await.expect("fails to setup db");
+
+    let count = Catalog::new(name.clone(), db)
+        .import(products)
+        .await
+        .expect("fails to import catalog");
+
+    println!("📦 imported {count} products into catalog '{name}'");
+}
@@ -0,0 +1,25 @@
+use dotenv::dotenv;
+use recipe_robot::model::vendor::fixture;
+use std::{net::SocketAddr, path::PathBuf};
+use tracing::info;
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    tracing_subscriber::fmt::init();
+
+    let dir = dotenv::var("VENDOR_FIXTURE_DIR").unwrap_or_else(|_| "fixtures".into());
+    let app = fixture::replay_router(PathBuf::from(&dir));
+
+    let port = dotenv::var("VENDOR_REPLAY_PORT")
+        .unwrap_or_else(|_| "1314".into())
+        .parse::<u16>()
+        .expect("invalid VENDOR_REPLAY_PORT");
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .expect("fails to bind listener");
+    info!("📼 replaying fixtures from {dir} on {addr}");
+
+    axum::serve(listener, app).await.expect("server fails");
+}
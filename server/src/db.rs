@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use surrealdb::sql::Datetime;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
@@ -15,21 +16,32 @@ pub struct Relation {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Recipe {
     pub text: String,
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    pub servings: Option<u32>,
+    pub source_url: Option<String>,
 }
 
+/// Canonical ingredient, keyed by `Ingredient::key`.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct IngredientDb {
-    name: String,
-    probably_at_home: bool,
+    pub name: String,
 }
 
-impl From<Ingredient> for IngredientDb {
-    fn from(ingredient: Ingredient) -> Self {
-        Self {
-            name: ingredient.name,
-            probably_at_home: ingredient.probably_at_home,
-        }
+/// Stores the canonical record of the ingredient unless it exists and returns its key.
+pub async fn store_ingredient(db: &Surreal<Any>, ingredient: &Ingredient) -> Result<String, Error> {
+    let key = ingredient.key();
+    if key.is_empty() {
+        return Err(Error::BadRequest("ingredient without name".to_string()));
     }
+
+    db.query("upsert type::thing('ingredient', $key) set name = name ?? $name")
+        .bind(("key", key.clone()))
+        .bind(("name", ingredient.name.trim().to_string()))
+        .await?
+        .check()?;
+
+    Ok(key)
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -37,7 +49,9 @@ pub struct Requires {
     pub r#in: Thing,
     pub out: Thing,
     pub quantity: f64,
-    pub unit: String,
+    pub unit: Unit,
+    pub probably_at_home: bool,
+    pub source_line: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -45,8 +59,10 @@ pub struct Seeks {
     pub r#in: Thing,
     pub out: Thing,
     pub quantity: f64,
-    pub unit: String,
+    pub unit: Unit,
     pub vendor: String,
+    pub probably_at_home: bool,
+    pub source_line: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -57,6 +73,71 @@ pub struct ItemDb {
     pub grammage: Option<String>,
     pub url: Option<String>,
     pub image_url: Option<String>,
+    #[serde(default)]
+    pub brand: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub category_path: Vec<String>,
+    #[serde(default)]
+    pub product_id: Option<String>,
+    #[serde(default)]
+    pub regular_price_cent: Option<i64>,
+    #[serde(default)]
+    pub offer: Option<OfferDb>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OfferDb {
+    pub price_cent: i64,
+    pub valid_from: Option<Datetime>,
+    pub valid_until: Option<Datetime>,
+    pub loyalty_only: bool,
+    pub conditions: Option<String>,
+}
+
+impl From<Offer> for OfferDb {
+    fn from(offer: Offer) -> Self {
+        Self {
+            price_cent: offer.price_cent,
+            valid_from: offer.valid_from.map(Datetime::from),
+            valid_until: offer.valid_until.map(Datetime::from),
+            loyalty_only: offer.loyalty_only,
+            conditions: offer.conditions,
+        }
+    }
+}
+
+impl From<OfferDb> for Offer {
+    fn from(offer: OfferDb) -> Self {
+        Self {
+            price_cent: offer.price_cent,
+            valid_from: offer.valid_from.map(|d| d.0),
+            valid_until: offer.valid_until.map(|d| d.0),
+            loyalty_only: offer.loyalty_only,
+            conditions: offer.conditions,
+        }
+    }
+}
+
+impl ItemDb {
+    pub fn into_item(self, id: String) -> Item {
+        Item {
+            id,
+            name: self.name,
+            grammage: self.grammage,
+            price_cent: self.price_cent,
+            url: self.url,
+            image_url: self.image_url,
+            brand: self.brand,
+            labels: self.labels,
+            category_path: self.category_path,
+            product_id: self.product_id,
+            regular_price_cent: self.regular_price_cent,
+            offer: self.offer.map(Offer::from),
+            ..Default::default()
+        }
+    }
 }
 
 impl From<(Item, &Vendor)> for ItemDb {
@@ -68,6 +149,63 @@ impl From<(Item, &Vendor)> for ItemDb {
             grammage: item.grammage,
             url: item.url,
             image_url: item.image_url,
+            brand: item.brand,
+            labels: item.labels,
+            category_path: item.category_path,
+            product_id: item.product_id,
+            regular_price_cent: item.regular_price_cent,
+            offer: item.offer.map(OfferDb::from),
         }
     }
 }
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Matches {
+    pub r#in: Thing,
+    pub out: Thing,
+    pub user: Option<Thing>,
+}
+
+/// Stores the item and returns the key of its record.
+pub async fn store_item(db: &Surreal<Any>, item: &Item, vendor: &Vendor) -> Result<String, Error> {
+    let item_id = item.id.to_string().replace("-", "");
+    let item_db: ItemDb = (item.clone(), vendor).into();
+    let Some(_item) = db
+        .upsert::<Option<ItemDb>>(("item", &item_id))
+        .content(item_db)
+        .await?
+    else {
+        error!("failed to store item");
+        return Err(Error::InternalServer);
+    };
+    Ok(item_id)
+}
+
+/// Stores the item and relates it to the ingredient it was matched to for the user.
+pub async fn store_item_match(
+    db: &Surreal<Any>,
+    item: &Item,
+    vendor: &Vendor,
+    ingredient_id: &str,
+    username: Option<&str>,
+) -> Result<(), Error> {
+    let item_id = store_item(db, item, vendor).await?;
+
+    // relate ingredient to item
+
+    let Some(_r) = db
+        .insert::<Vec<Matches>>("matches")
+        .relation(Matches {
+            r#in: thing(&format!("item:{item_id}"))?,
+            r#out: Thing::from(("ingredient", ingredient_id)),
+            user: username.map(|u| Thing::from(("user", u))),
+        })
+        .await?
+        .first()
+    else {
+        error!("failed to store item -> ingredient relation");
+        return Err(Error::InternalServer);
+    };
+
+    Ok(())
+}
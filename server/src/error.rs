@@ -1,6 +1,6 @@
 use axum;
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -22,10 +22,112 @@ pub enum Error {
     NotFound,
     Conflict(String),
     InternalServer,
+    Vendor(VendorError),
+}
+
+/// Failures of vendor apis, the `String` is the vendor name.
+#[derive(Debug, Clone)]
+pub enum VendorError {
+    Unreachable(String),
+    Timeout(String),
+    RateLimited {
+        vendor: String,
+        retry_after_seconds: u64,
+    },
+    SchemaChanged(String),
+    NoResults(String),
+}
+
+impl VendorError {
+    pub fn from_reqwest(vendor: &str, err: &reqwest::Error) -> Self {
+        if err.is_timeout() {
+            VendorError::Timeout(vendor.to_string())
+        } else if err.is_decode() {
+            VendorError::SchemaChanged(vendor.to_string())
+        } else {
+            VendorError::Unreachable(vendor.to_string())
+        }
+    }
+
+    /// Whether the vendor failed, as opposed to finding nothing.
+    pub fn is_unavailable(&self) -> bool {
+        !matches!(self, VendorError::NoResults(_))
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            VendorError::Unreachable(_) => "vendor_unreachable",
+            VendorError::Timeout(_) => "vendor_timeout",
+            VendorError::RateLimited { .. } => "vendor_rate_limited",
+            VendorError::SchemaChanged(_) => "vendor_schema_changed",
+            VendorError::NoResults(_) => "vendor_no_results",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            VendorError::Unreachable(_) | VendorError::SchemaChanged(_) => StatusCode::BAD_GATEWAY,
+            VendorError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            VendorError::RateLimited { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            VendorError::NoResults(_) => StatusCode::NOT_FOUND,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            VendorError::Unreachable(vendor) => format!("Vendor {vendor} is unreachable"),
+            VendorError::Timeout(vendor) => format!("Vendor {vendor} did not respond in time"),
+            VendorError::RateLimited { vendor, .. } => {
+                format!("Too many requests to vendor {vendor}")
+            }
+            VendorError::SchemaChanged(vendor) => {
+                format!("Unexpected response from vendor {vendor}")
+            }
+            VendorError::NoResults(vendor) => format!("No items found at vendor {vendor}"),
+        }
+    }
+}
+
+impl From<VendorError> for Error {
+    fn from(err: VendorError) -> Self {
+        Error::Vendor(err)
+    }
+}
+
+impl Error {
+    /// Whether the vendor failed, as opposed to finding nothing.
+    pub fn is_vendor_unavailable(&self) -> bool {
+        matches!(self, Error::Vendor(err) if err.is_unavailable())
+    }
+
+    /// Machine readable error code, part of every error response.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Unauthorized => "unauthorized",
+            Error::Forbidden(_) => "forbidden",
+            Error::PaymentRequired => "payment_required",
+            Error::TooManyRequests => "too_many_requests",
+            Error::BadRequest(_) => "bad_request",
+            Error::PayloadTooLarge => "payload_too_large",
+            Error::NotFound => "not_found",
+            Error::Conflict(_) => "conflict",
+            Error::InternalServer => "internal_server",
+            Error::Vendor(err) => err.code(),
+        }
+    }
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
+        let code = self.code();
+        let retry_after = match &self {
+            Error::Vendor(VendorError::RateLimited {
+                retry_after_seconds,
+                ..
+            }) => Some(*retry_after_seconds),
+            _ => None,
+        };
+
         let (status, error_message) = match self {
             Error::Unauthorized => (StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()),
             Error::Forbidden(error) => (StatusCode::FORBIDDEN, error),
@@ -47,11 +149,20 @@ impl IntoResponse for Error {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
             ),
+            Error::Vendor(err) => (err.status(), err.message()),
         };
         let body = Json(json!({
             "error": error_message,
+            "code": code,
         }));
-        (status, body).into_response()
+
+        let mut response = (status, body).into_response();
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
     }
 }
 
@@ -0,0 +1,47 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupIn {
+    ingredients: Vec<Ingredient>,
+    vendor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AisleOrderIn {
+    aisles: Vec<Aisle>,
+}
+
+/// Groups a shopping list by aisle, in the order of the vendor's stores.
+pub async fn group_ingredients(
+    _authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<GroupIn>,
+) -> Result<Json<Vec<AisleGroup>>, Error> {
+    let vendor = match payload.vendor {
+        Some(vendor) => vendor,
+        None => state.vendors.first().map(|v| v.name()).unwrap_or_default(),
+    };
+
+    let groups = Aisle::group(&state.db, &vendor, payload.ingredients).await?;
+    Ok(Json(groups))
+}
+
+pub async fn get_order(
+    _authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(vendor): Path<String>,
+) -> Result<Json<Vec<Aisle>>, Error> {
+    let aisles = Aisle::order(&state.db, &vendor).await?;
+    Ok(Json(aisles))
+}
+
+pub async fn update_order(
+    admin: AdminUser,
+    Extension(state): Extension<AppState>,
+    Path(vendor): Path<String>,
+    Json(payload): Json<AisleOrderIn>,
+) -> Result<Json<Vec<Aisle>>, Error> {
+    let aisles = Aisle::set_order(&state.db, &vendor, payload.aisles).await?;
+    info!("🛒 {} updated aisle order of {vendor}", admin.username);
+    Ok(Json(aisles))
+}
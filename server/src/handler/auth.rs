@@ -86,6 +86,34 @@ where
     }
 }
 
+/// Authenticated user listed in `ADMIN_USERNAMES` (comma separated).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AdminUser {
+    pub username: String,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminUser
+where
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(req: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthenticatedUser::from_request_parts(req, state).await?;
+
+        let admins = env::var("ADMIN_USERNAMES").unwrap_or_default();
+        if !admins.split(',').any(|a| a.trim() == user.username) {
+            warn!("🔒 user {} is not an admin", user.username);
+            return Err(Error::Forbidden("admin only".to_string()));
+        }
+
+        Ok(AdminUser {
+            username: user.username,
+        })
+    }
+}
+
 pub async fn join(
     Extension(state): Extension<AppState>,
     Json(payload): Json<SignupIn>,
@@ -0,0 +1,31 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackageAmountIn {
+    ingredient: Ingredient,
+    item: Item,
+}
+
+pub async fn get_package_amount(
+    _authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<PackageAmountIn>,
+) -> Result<Json<PackageAmount>, Error> {
+    let Some(amount) = payload
+        .ingredient
+        .package_amount(&state.db, &payload.item)
+        .await?
+    else {
+        return Err(Error::NotFound);
+    };
+
+    Ok(Json(amount))
+}
+
+pub async fn get_missing_densities(
+    _authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+) -> Result<Json<Vec<MissingDensity>>, Error> {
+    let missing = MissingDensity::all(&state.db).await?;
+    Ok(Json(missing))
+}
@@ -0,0 +1,20 @@
+use crate::prelude::*;
+
+pub async fn get_profile(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+) -> Result<Json<DietaryProfile>, Error> {
+    let profile = DietaryProfile::get(&state.db, &authenticated_user.username).await?;
+    Ok(Json(profile))
+}
+
+pub async fn update_profile(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<DietaryProfile>,
+) -> Result<Json<DietaryProfile>, Error> {
+    let profile = payload
+        .save(&state.db, &authenticated_user.username)
+        .await?;
+    Ok(Json(profile))
+}
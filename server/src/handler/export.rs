@@ -0,0 +1,44 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ExportQuery {
+    format: ExportFormat,
+}
+
+type ExportResponse = ([(http::HeaderName, String); 2], String);
+
+pub async fn export_recipe(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<ExportResponse, Error> {
+    let recipe = RecipeHistory::get(&state.db, &authenticated_user.username, &id).await?;
+    let export = Export::recipe(&state.db, recipe).await?;
+    respond(export, query.format)
+}
+
+pub async fn export_shopping_list(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<ExportResponse, Error> {
+    let list = ShoppingList::get(&state.db, &authenticated_user.username, &id).await?;
+    respond(list.into(), query.format)
+}
+
+fn respond(export: Export, format: ExportFormat) -> Result<ExportResponse, Error> {
+    let body = export.render(format)?;
+    Ok((
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", export.filename(format)),
+            ),
+        ],
+        body,
+    ))
+}
@@ -5,15 +5,27 @@ pub struct RecipeIn {
     text: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RecipeImportIn {
+    html: Option<String>,
+    url: Option<String>, // fetched unless `html` is given, stored as source either way
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IngredientsOut {
+    recipe_id: String,
+    title: Option<String>,
+    servings: Option<u32>,
     ingredients: Vec<Ingredient>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IngredientMatchIn {
     ingredient: Ingredient,
-    // vendor: Vendor,
+    vendor: Option<String>, // name of a configured vendor, defaults to the first one
+    #[serde(default)]
+    options: SearchOptions,
 }
 
 pub async fn get_recipe_ingredients(
@@ -21,11 +33,65 @@ pub async fn get_recipe_ingredients(
     Extension(state): Extension<AppState>,
     Json(payload): Json<RecipeIn>,
 ) -> Result<Json<IngredientsOut>, Error> {
-    // store recipe
-
     let recipe = Recipe {
         text: payload.text.clone(),
+        title: None,
+        notes: None,
+        servings: None,
+        source_url: None,
+    };
+    let out =
+        extract_ingredients(&state, &authenticated_user.username, recipe, &payload.text).await?;
+    Ok(Json(out))
+}
+
+/// Imports the schema.org recipe of a page, the ai only reads its ingredient lines.
+pub async fn import_recipe(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<RecipeImportIn>,
+) -> Result<Json<IngredientsOut>, Error> {
+    let imported = match (payload.html, payload.url) {
+        (Some(html), url) => ImportedRecipe::from_html(&html, url)?,
+        (None, Some(url)) => ImportedRecipe::fetch(&url).await?,
+        (None, None) => {
+            return Err(Error::BadRequest("html or url is required".to_string()));
+        }
+    };
+    info!(
+        "📥 {} imports {} ingredients from {}",
+        authenticated_user.username,
+        imported.ingredients.len(),
+        imported.source_url.as_deref().unwrap_or("html")
+    );
+
+    let recipe = Recipe {
+        text: imported.text(),
+        title: imported.title.clone(),
+        notes: None,
+        servings: imported.servings,
+        source_url: imported.source_url.clone(),
     };
+    let out = extract_ingredients(
+        &state,
+        &authenticated_user.username,
+        recipe,
+        &imported.ingredient_text(),
+    )
+    .await?;
+    Ok(Json(out))
+}
+
+/// Stores the recipe for the user and extracts its ingredients from `text`.
+async fn extract_ingredients(
+    state: &AppState,
+    username: &str,
+    recipe: Recipe,
+    text: &str,
+) -> Result<IngredientsOut, Error> {
+    // store recipe
+
+    let (title, servings) = (recipe.title.clone(), recipe.servings);
     let recipe_id = new_id();
     let Some(_r): Option<Recipe> = state
         .db
@@ -37,7 +103,6 @@ pub async fn get_recipe_ingredients(
         return Err(Error::InternalServer);
     };
 
-    let username = &authenticated_user.username;
     let Some(_r) = state
         .db
         .insert::<Vec<Relation>>("submits")
@@ -56,32 +121,51 @@ pub async fn get_recipe_ingredients(
 
     let ai = Ai::default();
     let ingredients = ai
-        .get_ingredients(&state.db, &authenticated_user.username, &payload.text)
+        .get_ingredients(&state.db, &username.to_string(), &text.to_string())
         .await?;
 
-    // store ingredients and relate them to recipe
+    // a recipe may list an ingredient twice, e.g. for the dough and the topping
+    let mut ingredients = Ingredient::merge(&state.db, ingredients).await?;
 
-    for ingredient in &ingredients {
-        let ingredient_db: IngredientDb = ingredient.clone().into();
+    // replace the ai's guess of what is at home with the user's pantry and habits
 
-        let ingredient_id = new_id();
-        let Some(_ingredient) = state
-            .db
-            .upsert::<Option<IngredientDb>>(("ingredient", &ingredient_id))
-            .content(ingredient_db)
+    Pantry::reconcile(&state.db, username, &mut ingredients).await?;
+
+    // flag ingredients that conflict with the user's diet, unflagged ingredients are still of use
+
+    let flagged: Result<(), Error> = async {
+        DietaryProfile::get(&state.db, username)
             .await?
-        else {
-            error!("failed to store ingredient");
-            return Err(Error::InternalServer);
-        };
+            .flag(&state.db, &username.to_string(), &mut ingredients)
+            .await
+    }
+    .await;
+    if let Err(err) = flagged {
+        error!("failed to flag diet conflicts of {username}'s ingredients: {err:?}");
+    }
 
-        let requires = Requires {
+    let keywords = AisleKeywords::load(&state.db).await?;
+    for ingredient in ingredients.iter_mut() {
+        ingredient.classify(&keywords);
+    }
+
+    // store ingredients and relate them to recipe
+
+    let mut relations: Vec<Requires> = vec![];
+    for ingredient in &ingredients {
+        let key = store_ingredient(&state.db, ingredient).await?;
+        let (quantity, unit) = ingredient.unit.to_canonical(ingredient.quantity);
+        relations.push(Requires {
             r#in: thing(&format!("recipe:{recipe_id}"))?,
-            out: thing(&format!("ingredient:{ingredient_id}"))?,
-            quantity: ingredient.quantity,
-            unit: ingredient.unit.clone(),
-        };
+            out: Thing::from(("ingredient", key.as_str())),
+            quantity,
+            unit,
+            probably_at_home: ingredient.probably_at_home,
+            source_line: ingredient.source_line.clone(),
+        });
+    }
 
+    for requires in relations {
         let Some(_r) = state
             .db
             .insert::<Vec<Requires>>("requires")
@@ -94,7 +178,12 @@ pub async fn get_recipe_ingredients(
         };
     }
 
-    Ok(Json(IngredientsOut { ingredients }))
+    Ok(IngredientsOut {
+        recipe_id,
+        title,
+        servings,
+        ingredients,
+    })
 }
 
 pub async fn get_items(
@@ -103,36 +192,33 @@ pub async fn get_items(
     Json(payload): Json<IngredientMatchIn>,
 ) -> Result<Json<Ingredient>, Error> {
     let mut ingredient = payload.ingredient.clone();
-    let vendor = Vendor::Rewe {
-        zip_code: "1237".to_string(),
-    }; // TODO get vendor from payload
+    let vendor = state.vendor(payload.vendor.as_deref())?;
 
-    // insert ingredient
+    // search the substitute for ingredients conflicting with the user's diet
 
-    let ingredient_db: IngredientDb = ingredient.clone().into();
-    let ingredient_id = new_id();
-    let Some(_ingredient) = state
-        .db
-        .upsert::<Option<IngredientDb>>(("ingredient", &ingredient_id))
-        .content(ingredient_db)
-        .await?
-    else {
-        error!("failed to store ingredient");
-        return Err(Error::InternalServer);
-    };
+    ingredient.use_substitute();
+    let profile = DietaryProfile::get(&state.db, &authenticated_user.username).await?;
+    let options = payload.options.clone().with_profile(&profile);
+
+    // store canonical ingredient
+
+    let ingredient_id = store_ingredient(&state.db, &ingredient).await?;
 
     // relate user to ingredient
 
     let username = &authenticated_user.username;
+    let (quantity, unit) = ingredient.unit.to_canonical(ingredient.quantity);
     let Some(_r) = state
         .db
         .insert::<Vec<Relation>>("seeks")
         .relation(Seeks {
             r#in: thing(&format!("user:{username}"))?,
-            out: thing(&format!("ingredient:{ingredient_id}"))?,
-            quantity: ingredient.quantity,
-            unit: ingredient.unit.clone(),
+            out: Thing::from(("ingredient", ingredient_id.as_str())),
+            quantity,
+            unit,
             vendor: vendor.name(),
+            probably_at_home: ingredient.probably_at_home,
+            source_line: ingredient.source_line.clone(),
         })
         .await?
         .first()
@@ -141,46 +227,43 @@ pub async fn get_items(
         return Err(Error::InternalServer);
     };
 
-    // find items at vendor
+    // find items at vendor and match one of them to the ingredient
 
-    vendor.find_items(&mut ingredient).await?;
+    let res = vendor
+        .find_and_match_item(
+            &state,
+            &authenticated_user.username,
+            &mut ingredient,
+            &options,
+        )
+        .await;
 
-    // match item to ingredient
+    // fall back to items matched in the past if vendor is unavailable
 
-    let ai = Ai::default();
-    ai.match_item(&state.db, &authenticated_user.username, &mut ingredient)
-        .await?;
+    if let Err(err) = res {
+        if !err.is_vendor_unavailable()
+            || !vendor
+                .use_stale_items(&state.db, username, &mut ingredient)
+                .await?
+        {
+            return Err(err);
+        }
 
-    // store item
+        vendor.refresh_in_background(
+            state.clone(),
+            username.to_string(),
+            ingredient.clone(),
+            ingredient_id,
+        );
+        return Ok(Json(ingredient));
+    }
 
-    if let Some(item) = &ingredient.item() {
-        let item_id = item.id.to_string().replace("-", "");
-        let item_db: ItemDb = (item.clone(), &vendor).into();
-        let Some(_item) = state
-            .db
-            .upsert::<Option<ItemDb>>(("item", &item_id))
-            .content(item_db)
-            .await?
-        else {
-            error!("failed to store item");
-            return Err(Error::InternalServer);
-        };
+    ingredient.classify(&AisleKeywords::load(&state.db).await?);
 
-        // relate ingredient to item
+    // store item
 
-        let Some(_r) = state
-            .db
-            .insert::<Vec<Relation>>("matches")
-            .relation(Relation {
-                r#in: thing(&format!("item:{item_id}"))?,
-                r#out: thing(&format!("ingredient:{ingredient_id}"))?,
-            })
-            .await?
-            .first()
-        else {
-            error!("failed to store item -> ingredient relation");
-            return Err(Error::InternalServer);
-        };
+    if let Some(item) = &ingredient.item() {
+        store_item_match(&state.db, item, vendor, &ingredient_id, Some(username)).await?;
     }
 
     Ok(Json(ingredient))
@@ -0,0 +1,52 @@
+use crate::prelude::*;
+
+pub async fn get_mappings(
+    _admin: AdminUser,
+    Extension(state): Extension<AppState>,
+) -> Result<Json<Vec<IngredientMapping>>, Error> {
+    let mappings = IngredientMapping::all(&state.db).await?;
+    Ok(Json(mappings))
+}
+
+pub async fn create_mapping(
+    admin: AdminUser,
+    Extension(state): Extension<AppState>,
+    Json(mut payload): Json<IngredientMapping>,
+) -> Result<Json<IngredientMapping>, Error> {
+    payload.id = None;
+    let mapping = payload.save(&state.db).await?;
+    info!(
+        "📖 {} created mapping for {}",
+        admin.username, mapping.canonical_name
+    );
+    Ok(Json(mapping))
+}
+
+pub async fn update_mapping(
+    admin: AdminUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+    Json(mut payload): Json<IngredientMapping>,
+) -> Result<Json<IngredientMapping>, Error> {
+    let Some(_mapping) = IngredientMapping::get(&state.db, &id).await? else {
+        return Err(Error::NotFound);
+    };
+
+    payload.id = Some(id);
+    let mapping = payload.save(&state.db).await?;
+    info!(
+        "📖 {} updated mapping for {}",
+        admin.username, mapping.canonical_name
+    );
+    Ok(Json(mapping))
+}
+
+pub async fn delete_mapping(
+    admin: AdminUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, Error> {
+    IngredientMapping::delete(&state.db, &id).await?;
+    info!("📖 {} deleted mapping {id}", admin.username);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ItemOut {
+    #[serde(flatten)]
+    item: Item,
+    vendor: String,
+    details: Option<ItemDetails>,
+}
+
+pub async fn get_item(
+    _authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ItemOut>, Error> {
+    let Some(item_db): Option<ItemDb> = state.db.select(("item", &id)).await? else {
+        return Err(Error::NotFound);
+    };
+
+    let vendor_name = item_db.vendor.clone();
+    let item = item_db.into_item(id);
+
+    // details are only available from configured vendors
+
+    let details = match state.vendors.iter().find(|v| v.name() == vendor_name) {
+        Some(vendor) => vendor.item_details(&state, &item).await?,
+        None => None,
+    };
+
+    Ok(Json(ItemOut {
+        item,
+        vendor: vendor_name,
+        details,
+    }))
+}
@@ -0,0 +1,60 @@
+use crate::prelude::*;
+
+pub async fn get_meal_plans(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+) -> Result<Json<Vec<MealPlan>>, Error> {
+    let plans = MealPlan::list(&state.db, &authenticated_user.username).await?;
+    Ok(Json(plans))
+}
+
+pub async fn create_meal_plan(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<MealPlanIn>,
+) -> Result<(StatusCode, Json<MealPlan>), Error> {
+    let plan = MealPlan::create(&state.db, &authenticated_user.username, payload).await?;
+    Ok((StatusCode::CREATED, Json(plan)))
+}
+
+pub async fn get_meal_plan(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<MealPlan>, Error> {
+    let plan = MealPlan::get(&state.db, &authenticated_user.username, &id).await?;
+    Ok(Json(plan))
+}
+
+pub async fn update_meal_plan(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<MealPlanIn>,
+) -> Result<Json<MealPlan>, Error> {
+    let plan = MealPlan::update(&state.db, &authenticated_user.username, &id, payload).await?;
+    Ok(Json(plan))
+}
+
+pub async fn delete_meal_plan(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, Error> {
+    MealPlan::delete(&state.db, &authenticated_user.username, &id).await?;
+    info!("🗑️ {} deleted meal plan {id}", authenticated_user.username);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// One list for all recipes of the plan, identical ingredients merged.
+pub async fn get_meal_plan_ingredients(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Ingredient>>, Error> {
+    let plan = MealPlan::get(&state.db, &authenticated_user.username, &id).await?;
+    let ingredients = plan
+        .ingredients(&state.db, &authenticated_user.username)
+        .await?;
+    Ok(Json(ingredients))
+}
@@ -1,2 +1,14 @@
+pub mod aisle;
 pub mod auth;
+pub mod conversion;
+pub mod diet;
+pub mod export;
 pub mod ingredient;
+pub mod ingredient_mapping;
+pub mod item;
+pub mod meal_plan;
+pub mod nutrition;
+pub mod pantry;
+pub mod recipe;
+pub mod shopping_list;
+pub mod vendor;
@@ -0,0 +1,26 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NutritionIn {
+    ingredients: Vec<Ingredient>,
+    vendor: Option<String>,
+    servings: Option<u32>,
+}
+
+pub async fn get_nutrition(
+    _authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<NutritionIn>,
+) -> Result<Json<NutritionEstimate>, Error> {
+    let vendor = state.vendor(payload.vendor.as_deref())?;
+
+    let estimate = NutritionEstimate::calculate(
+        &state,
+        vendor,
+        &payload.ingredients,
+        payload.servings.unwrap_or(1),
+    )
+    .await?;
+
+    Ok(Json(estimate))
+}
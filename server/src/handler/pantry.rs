@@ -0,0 +1,42 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemovedIn {
+    name: String,
+}
+
+pub async fn get_pantry(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+) -> Result<Json<Vec<PantryItem>>, Error> {
+    let items = Pantry::items(&state.db, &authenticated_user.username).await?;
+    Ok(Json(items))
+}
+
+pub async fn add_item(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<PantryItem>,
+) -> Result<Json<PantryItem>, Error> {
+    let item = Pantry::add(&state.db, &authenticated_user.username, payload).await?;
+    Ok(Json(item))
+}
+
+pub async fn remove_item(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(key): Path<String>,
+) -> Result<StatusCode, Error> {
+    Pantry::remove(&state.db, &authenticated_user.username, &key).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The user removed an ingredient from the shopping list, because it is at home.
+pub async fn removed_from_list(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<RemovedIn>,
+) -> Result<StatusCode, Error> {
+    Pantry::record_removal(&state.db, &authenticated_user.username, &payload.name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,59 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RecipesQuery {
+    page: usize,
+    limit: usize,
+}
+
+impl Default for RecipesQuery {
+    fn default() -> Self {
+        Self { page: 1, limit: 20 }
+    }
+}
+
+pub async fn get_recipes(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Query(query): Query<RecipesQuery>,
+) -> Result<Json<Vec<RecipeSummary>>, Error> {
+    let recipes = RecipeHistory::list(
+        &state.db,
+        &authenticated_user.username,
+        query.page,
+        query.limit,
+    )
+    .await?;
+    Ok(Json(recipes))
+}
+
+pub async fn get_recipe(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<RecipeDetail>, Error> {
+    let recipe = RecipeHistory::get(&state.db, &authenticated_user.username, &id).await?;
+    Ok(Json(recipe))
+}
+
+pub async fn update_recipe(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<RecipeUpdate>,
+) -> Result<Json<RecipeDetail>, Error> {
+    let recipe =
+        RecipeHistory::update(&state.db, &authenticated_user.username, &id, payload).await?;
+    Ok(Json(recipe))
+}
+
+pub async fn delete_recipe(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, Error> {
+    RecipeHistory::delete(&state.db, &authenticated_user.username, &id).await?;
+    info!("🗑️ {} deleted recipe {id}", authenticated_user.username);
+    Ok(StatusCode::NO_CONTENT)
+}
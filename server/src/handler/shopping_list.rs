@@ -0,0 +1,108 @@
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ShoppingListsQuery {
+    archived: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ShoppingListEntryIn {
+    #[serde(flatten)]
+    update: ShoppingListEntryUpdate,
+    #[serde(default)]
+    item_id: Option<String>, // e.g. of one of the `alternatives` of the ingredient
+    #[serde(default)]
+    search: Option<String>, // term the alternatives were found with, the entry's name otherwise
+    #[serde(default)]
+    vendor: Option<String>,
+}
+
+pub async fn get_shopping_lists(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Query(query): Query<ShoppingListsQuery>,
+) -> Result<Json<Vec<ShoppingList>>, Error> {
+    let lists = ShoppingList::list(&state.db, &authenticated_user.username, query.archived).await?;
+    Ok(Json(lists))
+}
+
+pub async fn create_shopping_list(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<NewShoppingList>,
+) -> Result<(StatusCode, Json<ShoppingList>), Error> {
+    let list = ShoppingList::create(&state.db, &authenticated_user.username, payload).await?;
+    Ok((StatusCode::CREATED, Json(list)))
+}
+
+pub async fn get_shopping_list(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ShoppingList>, Error> {
+    let list = ShoppingList::get(&state.db, &authenticated_user.username, &id).await?;
+    Ok(Json(list))
+}
+
+pub async fn archive_shopping_list(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ShoppingList>, Error> {
+    let list = ShoppingList::archive(&state.db, &authenticated_user.username, &id).await?;
+    info!(
+        "🗄️ {} archived shopping list {id}",
+        authenticated_user.username
+    );
+    Ok(Json(list))
+}
+
+pub async fn add_entry(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<NewShoppingListEntry>,
+) -> Result<Json<ShoppingList>, Error> {
+    let list =
+        ShoppingList::add_entry(&state.db, &authenticated_user.username, &id, payload).await?;
+    Ok(Json(list))
+}
+
+/// Changes quantities, checks the entry off or selects another item.
+pub async fn update_entry(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path((id, entry_id)): Path<(String, String)>,
+    Json(payload): Json<ShoppingListEntryIn>,
+) -> Result<Json<ShoppingList>, Error> {
+    let selected = match payload.item_id {
+        Some(item_id) => Some((
+            item_id,
+            payload.search,
+            state.vendor(payload.vendor.as_deref())?,
+        )),
+        None => None,
+    };
+
+    let list = ShoppingList::update_entry(
+        &state,
+        &authenticated_user.username,
+        &id,
+        &entry_id,
+        payload.update,
+        selected,
+    )
+    .await?;
+    Ok(Json(list))
+}
+
+pub async fn remove_entry(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Path((id, entry_id)): Path<(String, String)>,
+) -> Result<Json<ShoppingList>, Error> {
+    let list =
+        ShoppingList::remove_entry(&state.db, &authenticated_user.username, &id, &entry_id).await?;
+    Ok(Json(list))
+}
@@ -0,0 +1,144 @@
+use crate::prelude::*;
+use futures::future::join_all;
+
+const MAX_BASKET_INGREDIENTS: usize = 40;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IngredientVendorsIn {
+    ingredient: Ingredient,
+    #[serde(default)]
+    options: SearchOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BasketIn {
+    ingredients: Vec<Ingredient>,
+    #[serde(default)]
+    options: SearchOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Basket {
+    vendor: String,
+    ingredients: Vec<Ingredient>,
+    missing: Vec<String>,
+    price_total: f32,
+    savings: f32, // compared to regular prices
+}
+
+pub async fn get_items_per_vendor(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<IngredientVendorsIn>,
+) -> Result<Json<Vec<VendorItems>>, Error> {
+    let mut ingredient = payload.ingredient.clone();
+    ingredient.use_substitute();
+    let profile = DietaryProfile::get(&state.db, &authenticated_user.username).await?;
+    let options = payload.options.clone().with_profile(&profile);
+
+    let items = Vendor::find_items_at_all(&state, &ingredient, &options).await;
+
+    Ok(Json(items))
+}
+
+pub async fn get_baskets(
+    authenticated_user: AuthenticatedUser,
+    Extension(state): Extension<AppState>,
+    Json(payload): Json<BasketIn>,
+) -> Result<Json<Vec<Basket>>, Error> {
+    if payload.ingredients.is_empty() {
+        return Err(Error::BadRequest("no ingredients".to_string()));
+    }
+    if payload.ingredients.len() > MAX_BASKET_INGREDIENTS {
+        return Err(Error::BadRequest(format!(
+            "at most {MAX_BASKET_INGREDIENTS} ingredients per basket"
+        )));
+    }
+
+    let username = &authenticated_user.username;
+    let profile = DietaryProfile::get(&state.db, username).await?;
+    let options = payload.options.clone().with_profile(&profile);
+
+    // price the basket at every vendor concurrently
+
+    let baskets = state.vendors.iter().map(|vendor| {
+        let state = &state;
+        let ingredients = payload.ingredients.clone();
+        let options = &options;
+        async move {
+            let mut basket = Basket {
+                vendor: vendor.name(),
+                ingredients: vec![],
+                missing: vec![],
+                price_total: 0.0,
+                savings: 0.0,
+            };
+
+            for mut ingredient in ingredients {
+                ingredient.use_substitute();
+
+                // the ai only matches ingredients the user has no match for at this vendor yet
+
+                let reused = match vendor
+                    .use_matched_item(&state.db, username, &mut ingredient)
+                    .await
+                {
+                    Ok(reused) => reused,
+                    Err(err) => {
+                        warn!("fails to load matches at vendor {}: {err:?}", vendor.name());
+                        false
+                    }
+                };
+                if !reused {
+                    if let Err(err) = vendor
+                        .find_and_match_item(state, username, &mut ingredient, options)
+                        .await
+                    {
+                        warn!("fails to match item at vendor {}: {err:?}", vendor.name());
+                        basket.missing.push(ingredient.name.clone());
+                        continue;
+                    }
+
+                    // remembered so the next basket reuses the match
+                    if let Some(item) = ingredient.item() {
+                        let stored = match store_ingredient(&state.db, &ingredient).await {
+                            Ok(key) => {
+                                store_item_match(&state.db, &item, vendor, &key, Some(username))
+                                    .await
+                            }
+                            Err(err) => Err(err),
+                        };
+                        if let Err(err) = stored {
+                            warn!("fails to store basket match: {err:?}");
+                        }
+                    }
+                }
+
+                match ingredient.item() {
+                    Some(item) => {
+                        let pieces = ingredient.item_quantity.max(0) as usize;
+                        basket.price_total += item.price_total(pieces);
+                        basket.savings += item.savings(pieces);
+                    }
+                    None => basket.missing.push(ingredient.name.clone()),
+                }
+
+                basket.ingredients.push(ingredient);
+            }
+
+            basket
+        }
+    });
+    let mut baskets = join_all(baskets).await;
+
+    // most complete and cheapest basket first
+
+    baskets.sort_by(|a, b| {
+        a.missing
+            .len()
+            .cmp(&b.missing.len())
+            .then(a.price_total.total_cmp(&b.price_total))
+    });
+
+    Ok(Json(baskets))
+}
@@ -20,6 +20,25 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 pub struct AppState {
     pub db: Surreal<Any>,
     pub jwt_secret: String,
+    pub vendors: Vec<Vendor>,
+    pub vendor_http: VendorHttp,
+}
+
+impl AppState {
+    /// Configured vendor with the name, the first one if no name is given.
+    pub fn vendor(&self, name: Option<&str>) -> Result<&Vendor, Error> {
+        match name {
+            Some(name) => self
+                .vendors
+                .iter()
+                .find(|v| v.name() == name)
+                .ok_or_else(|| Error::BadRequest(format!("unknown vendor {name}"))),
+            None => self.vendors.first().ok_or_else(|| {
+                error!("no vendors configured");
+                Error::InternalServer
+            }),
+        }
+    }
 }
 
 pub async fn app() -> error::Result<Router> {
@@ -44,6 +63,7 @@ pub async fn app() -> error::Result<Router> {
         .allow_methods(vec![
             Method::GET,
             Method::POST,
+            Method::PUT,
             Method::DELETE,
             Method::PATCH,
         ])
@@ -61,8 +81,15 @@ pub async fn app() -> error::Result<Router> {
     let app_state = AppState {
         db,
         jwt_secret: jwt_secret.clone(),
+        vendors: Vendor::configured(),
+        vendor_http: VendorHttp::from_env().unwrap_or_else(|err| {
+            error!("🌐💥 error while setting up vendor http client: {err:?}");
+            std::process::exit(1);
+        }),
     };
 
+    Vendor::schedule_assortment_syncs(&app_state);
+
     let middleware_stack = ServiceBuilder::new()
         .layer(
             TraceLayer::new_for_http()
@@ -95,7 +122,100 @@ pub async fn app() -> error::Result<Router> {
             "/recipe/ingredients",
             post(handler::ingredient::get_recipe_ingredients),
         )
+        .route("/recipe/import", post(handler::ingredient::import_recipe))
         .route("/ingredient/items", post(handler::ingredient::get_items))
+        .route(
+            "/ingredient/package",
+            post(handler::conversion::get_package_amount),
+        )
+        .route("/item/:id", get(handler::item::get_item))
+        .route(
+            "/ingredients/grouped",
+            post(handler::aisle::group_ingredients),
+        )
+        .route("/aisle-order/:vendor", get(handler::aisle::get_order))
+        .route(
+            "/admin/aisle-order/:vendor",
+            put(handler::aisle::update_order),
+        )
+        .route(
+            "/dietary-profile",
+            get(handler::diet::get_profile).put(handler::diet::update_profile),
+        )
+        .route(
+            "/pantry",
+            get(handler::pantry::get_pantry).post(handler::pantry::add_item),
+        )
+        .route("/pantry/removed", post(handler::pantry::removed_from_list))
+        .route("/pantry/:key", delete(handler::pantry::remove_item))
+        .route(
+            "/admin/ingredient-mappings",
+            get(handler::ingredient_mapping::get_mappings)
+                .post(handler::ingredient_mapping::create_mapping),
+        )
+        .route(
+            "/admin/ingredient-mappings/:id",
+            put(handler::ingredient_mapping::update_mapping)
+                .delete(handler::ingredient_mapping::delete_mapping),
+        )
+        .route(
+            "/densities/missing",
+            get(handler::conversion::get_missing_densities),
+        )
+        .route(
+            "/ingredient/vendors/items",
+            post(handler::vendor::get_items_per_vendor),
+        )
+        .route("/recipe/baskets", post(handler::vendor::get_baskets))
+        .route("/recipe/nutrition", post(handler::nutrition::get_nutrition))
+        .route("/recipes", get(handler::recipe::get_recipes))
+        .route(
+            "/recipes/:id",
+            get(handler::recipe::get_recipe)
+                .patch(handler::recipe::update_recipe)
+                .delete(handler::recipe::delete_recipe),
+        )
+        .route("/recipes/:id/export", get(handler::export::export_recipe))
+        .route(
+            "/meal-plans",
+            get(handler::meal_plan::get_meal_plans).post(handler::meal_plan::create_meal_plan),
+        )
+        .route(
+            "/meal-plans/:id",
+            get(handler::meal_plan::get_meal_plan)
+                .put(handler::meal_plan::update_meal_plan)
+                .delete(handler::meal_plan::delete_meal_plan),
+        )
+        .route(
+            "/meal-plans/:id/ingredients",
+            get(handler::meal_plan::get_meal_plan_ingredients),
+        )
+        .route(
+            "/shopping-lists",
+            get(handler::shopping_list::get_shopping_lists)
+                .post(handler::shopping_list::create_shopping_list),
+        )
+        .route(
+            "/shopping-lists/:id",
+            get(handler::shopping_list::get_shopping_list),
+        )
+        .route(
+            "/shopping-lists/:id/export",
+            get(handler::export::export_shopping_list),
+        )
+        .route(
+            "/shopping-lists/:id/archive",
+            post(handler::shopping_list::archive_shopping_list),
+        )
+        .route(
+            "/shopping-lists/:id/entries",
+            post(handler::shopping_list::add_entry),
+        )
+        .route(
+            "/shopping-lists/:id/entries/:entry_id",
+            patch(handler::shopping_list::update_entry)
+                .delete(handler::shopping_list::remove_entry),
+        )
         .layer(middleware_stack);
 
     Ok(app)
@@ -105,7 +225,7 @@ async fn health() -> error::Result<StatusCode> {
     Ok(StatusCode::OK)
 }
 
-async fn setup_db() -> Result<Surreal<Any>> {
+pub async fn setup_db() -> Result<Surreal<Any>> {
     let db_url = std::env::var("DB_URL").expect("DB_URL must be set");
     let db = connect(db_url).await.context("fails to connect to db")?;
 
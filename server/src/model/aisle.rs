@@ -0,0 +1,280 @@
+use super::ingredient_mapping::normalize;
+use crate::prelude::*;
+
+/// Section of a supermarket.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Aisle {
+    Produce,
+    Bakery,
+    Dairy,
+    Meat,
+    Fish,
+    Frozen,
+    DryGoods,
+    Canned,
+    Spices,
+    Sauces,
+    Sweets,
+    Beverages,
+    Other,
+}
+
+/// Order of the aisles in a typical German supermarket, used for vendors without a configured order.
+pub const DEFAULT_AISLE_ORDER: &[Aisle] = &[
+    Aisle::Produce,
+    Aisle::Bakery,
+    Aisle::Meat,
+    Aisle::Fish,
+    Aisle::Dairy,
+    Aisle::DryGoods,
+    Aisle::Canned,
+    Aisle::Sauces,
+    Aisle::Spices,
+    Aisle::Sweets,
+    Aisle::Beverages,
+    Aisle::Frozen,
+    Aisle::Other,
+];
+
+/// Normalized words in vendor category paths, the top level segment of a path is checked first.
+/// Keywords of four or more letters also match at the start of a word, e.g. `milch` in `Milchprodukte`.
+const CATEGORY_KEYWORDS: &[(&str, Aisle)] = &[
+    ("tiefkuhl", Aisle::Frozen),
+    ("obst", Aisle::Produce),
+    ("gemuse", Aisle::Produce),
+    ("krauter", Aisle::Produce),
+    ("brot", Aisle::Bakery),
+    ("backwaren", Aisle::Bakery),
+    ("kase", Aisle::Dairy),
+    ("molkerei", Aisle::Dairy),
+    ("milch", Aisle::Dairy),
+    ("joghurt", Aisle::Dairy),
+    ("eier", Aisle::Dairy),
+    ("fleisch", Aisle::Meat),
+    ("wurst", Aisle::Meat),
+    ("geflugel", Aisle::Meat),
+    ("fisch", Aisle::Fish),
+    ("meeresfruchte", Aisle::Fish),
+    ("gewurz", Aisle::Spices),
+    ("konserve", Aisle::Canned),
+    ("saucen", Aisle::Sauces),
+    ("sossen", Aisle::Sauces),
+    ("dressing", Aisle::Sauces),
+    ("susswaren", Aisle::Sweets),
+    ("sussigkeiten", Aisle::Sweets),
+    ("snack", Aisle::Sweets),
+    ("getranke", Aisle::Beverages),
+    ("kaffee", Aisle::Beverages),
+    ("tee", Aisle::Beverages),
+    ("nudeln", Aisle::DryGoods),
+    ("teigwaren", Aisle::DryGoods),
+    ("reis", Aisle::DryGoods),
+    ("backzutaten", Aisle::DryGoods),
+    ("mehl", Aisle::DryGoods),
+    ("ol", Aisle::DryGoods),
+    ("ole", Aisle::DryGoods),
+    ("grundnahrungsmittel", Aisle::DryGoods),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+struct AisleKeyword {
+    keyword: String,
+    aisle: Aisle,
+}
+
+/// Local classification table of ingredient names, load it once per request.
+#[derive(Debug, Clone)]
+pub struct AisleKeywords(Vec<AisleKeyword>);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AisleOrder {
+    pub aisles: Vec<Aisle>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AisleGroup {
+    pub aisle: Aisle,
+    pub ingredients: Vec<Ingredient>,
+}
+
+impl Aisle {
+    /// Aisle of a vendor category path like `["Obst & Gemüse", "Gemüse", "Zwiebeln"]`.
+    pub fn from_category_path(path: &[String]) -> Option<Aisle> {
+        path.iter().find_map(|segment| {
+            let segment = normalize(segment);
+            let words = segment
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .collect::<Vec<_>>();
+            CATEGORY_KEYWORDS
+                .iter()
+                .find(|(keyword, _)| {
+                    words.iter().any(|w| {
+                        w == keyword || (keyword.chars().count() >= 4 && w.starts_with(keyword))
+                    })
+                })
+                .map(|(_, aisle)| *aisle)
+        })
+    }
+
+    /// Aisle order of the vendor's stores.
+    pub async fn order(db: &Surreal<Any>, vendor: &str) -> Result<Vec<Aisle>, Error> {
+        let order: Option<AisleOrder> = db.select(("aisle_order", vendor)).await?;
+        let mut aisles = order.map(|o| o.aisles).unwrap_or_default();
+
+        // aisles missing in a configured order go last
+        for aisle in DEFAULT_AISLE_ORDER {
+            if !aisles.contains(aisle) {
+                aisles.push(*aisle);
+            }
+        }
+
+        Ok(aisles)
+    }
+
+    pub async fn set_order(
+        db: &Surreal<Any>,
+        vendor: &str,
+        aisles: Vec<Aisle>,
+    ) -> Result<Vec<Aisle>, Error> {
+        let mut unique: Vec<Aisle> = vec![];
+        for aisle in aisles {
+            if !unique.contains(&aisle) {
+                unique.push(aisle);
+            }
+        }
+
+        let Some(_order): Option<AisleOrder> = db
+            .upsert(("aisle_order", vendor))
+            .content(AisleOrder { aisles: unique })
+            .await?
+        else {
+            error!("failed to store aisle order");
+            return Err(Error::InternalServer);
+        };
+
+        Self::order(db, vendor).await
+    }
+
+    /// Groups the ingredients by aisle, in the aisle order of the vendor's stores.
+    pub async fn group(
+        db: &Surreal<Any>,
+        vendor: &str,
+        ingredients: Vec<Ingredient>,
+    ) -> Result<Vec<AisleGroup>, Error> {
+        let order = Self::order(db, vendor).await?;
+        let keywords = AisleKeywords::load(db).await?;
+
+        let mut groups: Vec<AisleGroup> = order
+            .into_iter()
+            .map(|aisle| AisleGroup {
+                aisle,
+                ingredients: vec![],
+            })
+            .collect();
+
+        for mut ingredient in ingredients {
+            if ingredient.aisle.is_none() {
+                ingredient.classify(&keywords);
+            }
+            let aisle = ingredient.aisle.unwrap_or(Aisle::Other);
+            if let Some(group) = groups.iter_mut().find(|g| g.aisle == aisle) {
+                group.ingredients.push(ingredient);
+            }
+        }
+
+        groups.retain(|g| !g.ingredients.is_empty());
+        Ok(groups)
+    }
+}
+
+impl AisleKeywords {
+    pub async fn load(db: &Surreal<Any>) -> Result<AisleKeywords, Error> {
+        let keywords: Vec<AisleKeyword> = db
+            .query("select keyword, aisle from aisle_keyword")
+            .await?
+            .take(0)?;
+        Ok(AisleKeywords(keywords))
+    }
+
+    /// Aisle of an ingredient name. Keywords match at the start or end of a word,
+    /// e.g. `mehl` in `Weizenmehl`, and the longest matching keyword wins.
+    pub fn classify(&self, name: &str) -> Option<Aisle> {
+        let name = normalize(name);
+        self.0
+            .iter()
+            .filter(|k| {
+                name.contains(&k.keyword)
+                    && (k.keyword.contains(' ')
+                        || name
+                            .split(|c: char| !c.is_alphanumeric())
+                            .any(|w| w.starts_with(&k.keyword) || w.ends_with(&k.keyword)))
+            })
+            .max_by_key(|k| k.keyword.len())
+            .map(|k| k.aisle)
+    }
+}
+
+impl Ingredient {
+    /// Sets the aisle from the category of the matched item, or from the name of the ingredient.
+    pub fn classify(&mut self, keywords: &AisleKeywords) {
+        let from_item = self.item().and_then(|i| {
+            i.aisle
+                .or_else(|| Aisle::from_category_path(&i.category_path))
+        });
+
+        self.aisle = Some(
+            from_item
+                .or_else(|| keywords.classify(&self.name))
+                .unwrap_or(Aisle::Other),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aisle(path: &str) -> Option<Aisle> {
+        Aisle::from_category_path(&path.split('/').map(String::from).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn classifies_category_paths_top_level_first() {
+        assert_eq!(
+            aisle("Obst & Gemüse/Gemüse/Süßkartoffeln"),
+            Some(Aisle::Produce)
+        );
+        assert_eq!(aisle("Tiefkühl/Gemüse/Erbsen"), Some(Aisle::Frozen));
+        assert_eq!(aisle("Getränke/Bier/Kölsch"), Some(Aisle::Beverages));
+        assert_eq!(aisle("Süßwaren/Schokolade"), Some(Aisle::Sweets));
+        assert_eq!(aisle("Kochen & Backen/Öle & Essig"), Some(Aisle::DryGoods));
+        assert_eq!(aisle("Milchprodukte/Joghurt"), Some(Aisle::Dairy));
+        assert_eq!(aisle("Süßkartoffeln"), None);
+        assert_eq!(aisle("Kölsch"), None);
+        assert_eq!(aisle("Teebutter"), None);
+    }
+
+    #[test]
+    fn classifies_names_by_words() {
+        let keywords = AisleKeywords(
+            [
+                ("tk", Aisle::Frozen),
+                ("mehl", Aisle::DryGoods),
+                ("ei", Aisle::Dairy),
+            ]
+            .into_iter()
+            .map(|(keyword, aisle)| AisleKeyword {
+                keyword: keyword.to_string(),
+                aisle,
+            })
+            .collect(),
+        );
+
+        assert_eq!(keywords.classify("TK-Erbsen"), Some(Aisle::Frozen));
+        assert_eq!(keywords.classify("Weizenmehl"), Some(Aisle::DryGoods));
+        assert_eq!(keywords.classify("Eier"), Some(Aisle::Dairy));
+        assert_eq!(keywords.classify("Zwiebel"), None);
+    }
+}
@@ -0,0 +1,216 @@
+use super::ingredient_mapping::normalize;
+use crate::prelude::*;
+use regex::Regex;
+use std::sync::LazyLock;
+use surrealdb::sql::Datetime;
+
+/// Package content at the start of a grammage, e.g. `4 x 125g` or `ca. 1,5 kg`.
+static PACKAGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:ca\.?\s*)?(?:(\d+)\s*[xX×]\s*)?(\d+(?:[.,]\d+)?)\s*([a-zA-ZäöüÄÖÜ]+)")
+        .expect("invalid package regex")
+});
+
+/// Density and piece weight of an ingredient, e.g. 0.55 g/ml for flour or 60 g per egg.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Density {
+    pub name: String,
+    pub grams_per_ml: Option<f64>,
+    pub grams_per_piece: Option<f64>,
+}
+
+/// Ingredient a conversion failed for, so the density table can grow.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MissingDensity {
+    pub name: String,
+    pub from_unit: String,
+    pub to_unit: String,
+    pub count: i64,
+    pub last_seen: Datetime,
+}
+
+/// Quantity of an ingredient expressed in the package of an item.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct PackageAmount {
+    pub quantity: f64, // in the package unit
+    pub unit: Unit,
+    pub package_quantity: f64,
+    pub packages: f64,
+    pub pieces_required: i64, // packages to buy
+}
+
+impl Density {
+    /// Density keyed by the normalized name, like ingredients and mappings.
+    pub async fn find(db: &Surreal<Any>, name: &str) -> Result<Option<Density>, Error> {
+        let density = db.select(("density", normalize(name))).await?;
+        Ok(density)
+    }
+
+    /// Converts between dimensions, within a dimension use `Unit::convert`.
+    pub fn convert(&self, quantity: f64, from: Unit, to: Unit) -> Option<f64> {
+        let (quantity, from) = from.to_canonical(quantity);
+        let grams = match from {
+            Unit::Gram => quantity,
+            Unit::Milliliter => quantity * self.grams_per_ml?,
+            Unit::Piece => quantity * self.grams_per_piece?,
+            _ => return None,
+        };
+
+        let (factor, to) = to.to_canonical(1.0);
+        let quantity = match to {
+            Unit::Gram => grams,
+            Unit::Milliliter => grams / self.grams_per_ml?,
+            Unit::Piece => grams / self.grams_per_piece?,
+            _ => return None,
+        };
+        Some(quantity / factor)
+    }
+}
+
+impl MissingDensity {
+    pub async fn report(db: &Surreal<Any>, name: &str, from: Unit, to: Unit) -> Result<(), Error> {
+        warn!("⚖️ no density to convert {name} from {from} to {to}");
+        db.query(
+            "upsert type::thing('missing_density', $key) set name = $name, from_unit = $from, to_unit = $to, count += 1, last_seen = time::now()",
+        )
+        .bind(("key", normalize(name)))
+        .bind(("name", name.to_string()))
+        .bind(("from", from.to_string()))
+        .bind(("to", to.to_string()))
+        .await?
+        .check()?;
+        Ok(())
+    }
+
+    pub async fn all(db: &Surreal<Any>) -> Result<Vec<MissingDensity>, Error> {
+        let missing: Vec<MissingDensity> = db
+            .query("select * from missing_density order by count desc, last_seen desc")
+            .await?
+            .take(0)?;
+        Ok(missing)
+    }
+}
+
+impl Item {
+    /// Content of one package parsed from the grammage, e.g. `(500.0, Unit::Gram)` for `4 x 125g`.
+    pub fn package(&self) -> Option<(f64, Unit)> {
+        let captures = PACKAGE.captures(self.grammage.as_ref()?)?;
+        let count = captures
+            .get(1)
+            .and_then(|c| c.as_str().parse::<f64>().ok())
+            .unwrap_or(1.0);
+        let quantity = captures
+            .get(2)?
+            .as_str()
+            .replace(',', ".")
+            .parse::<f64>()
+            .ok()?;
+        let unit = captures.get(3)?.as_str().parse::<Unit>().ok()?;
+
+        Some((count * quantity, unit))
+    }
+}
+
+impl Ingredient {
+    /// Quantity of the ingredient in the unit, using the density table to convert between mass,
+    /// volume and pieces.
+    pub async fn quantity_in(&self, db: &Surreal<Any>, unit: Unit) -> Result<Option<f64>, Error> {
+        self.convert_quantity(db, self.quantity, self.unit, unit)
+            .await
+    }
+
+    /// Another quantity of the ingredient converted, e.g. the stock in the pantry.
+    pub async fn convert_quantity(
+        &self,
+        db: &Surreal<Any>,
+        quantity: f64,
+        from: Unit,
+        to: Unit,
+    ) -> Result<Option<f64>, Error> {
+        if let Some(quantity) = from.convert(quantity, to) {
+            return Ok(Some(quantity));
+        }
+        Ok(Density::find(db, &self.name)
+            .await?
+            .and_then(|density| density.convert(quantity, from, to)))
+    }
+
+    /// Quantity to buy in the package unit of the item. Missing densities are reported.
+    pub async fn package_amount(
+        &self,
+        db: &Surreal<Any>,
+        item: &Item,
+    ) -> Result<Option<PackageAmount>, Error> {
+        let amount = self.package_conversion(db, item).await?;
+
+        // the report only grows the density table, the match works without it
+        if let (None, Some((_, package_unit))) = (&amount, item.package()) {
+            if let Err(err) = MissingDensity::report(db, &self.name, self.unit, package_unit).await
+            {
+                error!("failed to report missing density of {}: {err:?}", self.name);
+            }
+        }
+
+        Ok(amount)
+    }
+
+    /// Quantity to buy in the package unit of the item, without writing to the db.
+    async fn package_conversion(
+        &self,
+        db: &Surreal<Any>,
+        item: &Item,
+    ) -> Result<Option<PackageAmount>, Error> {
+        let Some((package_quantity, package_unit)) = item.package() else {
+            debug!("no package size in grammage of {}", item.name);
+            return Ok(None);
+        };
+        let Some(quantity) = self
+            .convert_quantity(db, self.quantity_to_buy(), self.unit, package_unit)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let packages = quantity / package_quantity;
+        Ok(Some(PackageAmount {
+            quantity,
+            unit: package_unit,
+            package_quantity,
+            packages,
+            pieces_required: (packages.ceil() as i64).max(1),
+        }))
+    }
+
+    /// Packages of the selected item to buy, the current `item_quantity` if the package size
+    /// is unknown. Read only, missing densities are reported when items are matched.
+    pub async fn packages_required(&self, db: &Surreal<Any>) -> Result<i64, Error> {
+        let Some(item) = self.item() else {
+            return Ok(0);
+        };
+        Ok(match self.package_conversion(db, &item).await? {
+            Some(amount) => amount.pieces_required,
+            None => self.item_quantity.max(1),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(grammage: &str) -> Option<(f64, Unit)> {
+        Item {
+            grammage: Some(grammage.to_string()),
+            ..Default::default()
+        }
+        .package()
+    }
+
+    #[test]
+    fn parses_package_from_grammage() {
+        assert_eq!(package("4 x 125g"), Some((500.0, Unit::Gram)));
+        assert_eq!(package("ca. 1,5 kg"), Some((1.5, Unit::Kilogram)));
+        assert_eq!(package("500g (1 kg = 3,98 €)"), Some((500.0, Unit::Gram)));
+        assert_eq!(package("1 Bund"), Some((1.0, Unit::Bunch)));
+        assert_eq!(package("lose"), None);
+    }
+}
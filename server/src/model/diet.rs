@@ -0,0 +1,288 @@
+use super::ingredient_mapping::normalize;
+use crate::prelude::*;
+
+/// Normalized words of meat and fish categories, e.g. `Fleisch & Fisch`.
+const MEAT_CATEGORIES: &[&str] = &[
+    "fleisch",
+    "fleischwaren",
+    "wurst",
+    "wurstwaren",
+    "aufschnitt",
+    "geflugel",
+    "fisch",
+    "meeresfruchte",
+];
+
+/// Normalized meat and fish words found at the start or end of words of product names,
+/// e.g. `Hähnchenbrust`, `Speckwürfel` or `Bratwurst`.
+const MEAT_NAMES: &[&str] = &[
+    "hahnchen",
+    "huhn",
+    "pute",
+    "puten",
+    "rind",
+    "rinder",
+    "schwein",
+    "schweine",
+    "kalb",
+    "lamm",
+    "hack",
+    "speck",
+    "schinken",
+    "salami",
+    "wurst",
+    "wurstchen",
+    "bacon",
+    "lachs",
+    "fisch",
+    "garnelen",
+];
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Diet {
+    Vegan,
+    Vegetarian,
+    LactoseFree,
+    GlutenFree,
+}
+
+impl Diet {
+    /// German name, as used in prompts and product labels.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Diet::Vegan => "vegan",
+            Diet::Vegetarian => "vegetarisch",
+            Diet::LactoseFree => "laktosefrei",
+            Diet::GlutenFree => "glutenfrei",
+        }
+    }
+
+    /// Words of declared allergens the diet excludes, unless the product is labeled for the diet.
+    fn allergens(&self) -> &'static [&'static str] {
+        const MILK: &[&str] = &["milch", "milcherzeugnisse", "laktose", "lactose"];
+        match self {
+            Diet::Vegan => &[
+                "milch",
+                "milcherzeugnisse",
+                "laktose",
+                "lactose",
+                "ei",
+                "eier",
+                "eierzeugnisse",
+                "fisch",
+                "fischerzeugnisse",
+                "krebstiere",
+                "weichtiere",
+            ],
+            Diet::Vegetarian => &["fisch", "fischerzeugnisse", "krebstiere", "weichtiere"],
+            Diet::LactoseFree => MILK,
+            Diet::GlutenFree => &[
+                "gluten",
+                "glutenhaltiges",
+                "glutenhaltige",
+                "weizen",
+                "roggen",
+                "gerste",
+                "dinkel",
+                "hafer",
+            ],
+        }
+    }
+
+    fn labeled(&self, labels: &[String]) -> bool {
+        labels.iter().any(|l| {
+            let l = l.to_lowercase();
+            l.contains(self.label()) || (*self == Diet::Vegetarian && l.contains("vegan"))
+        })
+    }
+
+    /// Whether the item is excluded by the diet because it is meat or fish, by its categories or
+    /// name. Works without the item's details.
+    pub fn excludes(&self, item: &Item) -> bool {
+        if !matches!(self, Diet::Vegan | Diet::Vegetarian) || self.labeled(&item.labels) {
+            return false;
+        }
+
+        let words = normalize(&item.name)
+            .split(|c: char| !c.is_alphanumeric())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        if words
+            .iter()
+            .any(|w| w.starts_with("vegan") || w.starts_with("vegetari"))
+        {
+            return false; // e.g. `Vegane Bratwurst`
+        }
+
+        let category = item.category_path.iter().any(|c| {
+            normalize(c)
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| MEAT_CATEGORIES.contains(&word))
+        });
+        let name = words.iter().any(|word| {
+            MEAT_NAMES
+                .iter()
+                .any(|meat| word.starts_with(meat) || word.ends_with(meat))
+        });
+        category || name
+    }
+
+    /// Whether the item conflicts with the diet according to its labels, categories, name and
+    /// declared allergens.
+    pub fn conflicts_with(&self, item: &Item, details: &ItemDetails) -> bool {
+        if self.labeled(&item.labels) || self.labeled(&details.labels) {
+            return false;
+        }
+        if self.excludes(item) {
+            return true;
+        }
+
+        details.allergens.iter().any(|a| {
+            let a = a.to_lowercase();
+            a.split(|c: char| !c.is_alphanumeric())
+                .any(|word| self.allergens().contains(&word))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+#[serde(default)]
+pub struct DietaryProfile {
+    pub diets: Vec<Diet>,
+    pub allergens: Vec<String>,
+    pub disliked: Vec<String>, // ingredient names
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct DietaryProfileDb {
+    user: Thing,
+    diets: Vec<Diet>,
+    allergens: Vec<String>,
+    disliked: Vec<String>,
+}
+
+/// Conflict of an ingredient with a profile as judged by the ai.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DietConflict {
+    pub name: String,
+    #[serde(default)]
+    pub conflicts: Vec<String>,
+    pub substitute: Option<String>,
+}
+
+impl DietaryProfile {
+    pub fn is_empty(&self) -> bool {
+        self.diets.is_empty() && self.allergens.is_empty() && self.disliked.is_empty()
+    }
+
+    pub async fn get(db: &Surreal<Any>, username: &str) -> Result<DietaryProfile, Error> {
+        let profile: Option<DietaryProfileDb> = db.select(("dietary_profile", username)).await?;
+        Ok(profile
+            .map(|p| DietaryProfile {
+                diets: p.diets,
+                allergens: p.allergens,
+                disliked: p.disliked,
+            })
+            .unwrap_or_default())
+    }
+
+    pub async fn save(self, db: &Surreal<Any>, username: &str) -> Result<DietaryProfile, Error> {
+        let clean = |values: Vec<String>| -> Vec<String> {
+            values
+                .into_iter()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+
+        let Some(_profile): Option<DietaryProfileDb> = db
+            .upsert(("dietary_profile", username))
+            .content(DietaryProfileDb {
+                user: thing(&format!("user:{username}"))?,
+                diets: self.diets.clone(),
+                allergens: clean(self.allergens.clone()),
+                disliked: clean(self.disliked.clone()),
+            })
+            .await?
+        else {
+            error!("failed to store dietary profile");
+            return Err(Error::InternalServer);
+        };
+
+        Self::get(db, username).await
+    }
+
+    /// Flags ingredients that conflict with the profile and suggests substitutes.
+    /// Disliked ingredients are flagged even if the ai fails to judge the others.
+    pub async fn flag(
+        &self,
+        db: &Surreal<Any>,
+        username: &String,
+        ingredients: &mut [Ingredient],
+    ) -> Result<(), Error> {
+        if self.is_empty() || ingredients.is_empty() {
+            return Ok(());
+        }
+
+        let conflicts = match Ai::default()
+            .check_diet(db, username, self, ingredients)
+            .await
+        {
+            Ok(conflicts) => conflicts,
+            Err(err) => {
+                warn!("failed to check ingredients against the dietary profile of {username}: {err:?}");
+                vec![]
+            }
+        };
+
+        let disliked = self
+            .disliked
+            .iter()
+            .map(|d| normalize(d))
+            .collect::<Vec<_>>();
+        for ingredient in ingredients.iter_mut() {
+            let name = normalize(&ingredient.name);
+            if let Some(conflict) = conflicts.iter().find(|c| normalize(&c.name) == name) {
+                ingredient.diet_conflicts = conflict.conflicts.clone();
+                ingredient.substitute = conflict.substitute.clone();
+            }
+            if disliked.iter().any(|d| name.contains(d.as_str()))
+                && !ingredient.diet_conflicts.iter().any(|c| c == "disliked")
+            {
+                ingredient.diet_conflicts.push("disliked".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, category_path: &[&str]) -> Item {
+        Item {
+            name: name.to_string(),
+            category_path: category_path.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn vegetarians_exclude_meat_by_name_and_category() {
+        let excluded = |name, category_path| Diet::Vegetarian.excludes(&item(name, category_path));
+
+        assert!(excluded("Hähnchenbrust", &[]));
+        assert!(excluded("Speckwürfel", &[]));
+        assert!(excluded("Rostbratwurst", &[]));
+        assert!(excluded("Gut&Günstig Aufschnitt", &["Wurst & Aufschnitt"]));
+        assert!(excluded("Zarte Filets", &["Fleisch & Fisch", "Geflügel"]));
+
+        assert!(!excluded("Fleischtomaten", &["Obst & Gemüse"]));
+        assert!(!excluded("Flammkuchen", &["Tiefkühl"]));
+        assert!(!excluded("Vegane Bratwurst", &["Fleischalternativen"]));
+        assert!(!Diet::LactoseFree.excludes(&item("Hähnchenbrust", &[])));
+    }
+}
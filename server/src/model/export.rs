@@ -0,0 +1,396 @@
+use super::ingredient_mapping::normalize;
+use crate::prelude::*;
+
+const DEFAULT_TITLE: &str = "Einkaufsliste";
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Csv,
+    Text,
+    JsonLd,
+}
+
+/// Matched items of a recipe or shopping list, rendered for download.
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub title: String,
+    pub lines: Vec<ExportLine>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportLine {
+    pub name: String,
+    pub quantity: Option<f64>,
+    pub unit: Option<Unit>,
+    pub item: Option<Item>,
+    pub pieces: usize,
+    pub checked: bool,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Text => "text/plain; charset=utf-8",
+            ExportFormat::JsonLd => "application/ld+json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Text => "txt",
+            ExportFormat::JsonLd => "jsonld",
+        }
+    }
+}
+
+impl ExportLine {
+    /// Quantity and unit in front of the name, e.g. `2 Stück Zwiebel`.
+    fn label(&self) -> String {
+        match (self.quantity, self.unit) {
+            (Some(quantity), Some(unit)) => {
+                format!("{} {} {}", format_quantity(quantity), unit, self.name)
+            }
+            (Some(quantity), None) => format!("{} {}", format_quantity(quantity), self.name),
+            _ => self.name.clone(),
+        }
+    }
+
+    fn price_total_string(&self) -> Option<String> {
+        self.item
+            .as_ref()
+            .map(|i| i.price_total_string(self.pieces))
+    }
+}
+
+impl From<ShoppingList> for Export {
+    fn from(list: ShoppingList) -> Self {
+        Self {
+            title: list.title.unwrap_or_else(|| DEFAULT_TITLE.to_string()),
+            lines: list
+                .entries
+                .into_iter()
+                .map(|e| ExportLine {
+                    name: e.name,
+                    quantity: e.quantity,
+                    unit: e.unit,
+                    item: e.item,
+                    pieces: e.item_quantity.max(0) as usize,
+                    checked: e.checked,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Export {
+    /// Lines of the recipe's ingredients with the packages to buy of the matched items.
+    pub async fn recipe(db: &Surreal<Any>, recipe: RecipeDetail) -> Result<Self, Error> {
+        let mut lines = vec![];
+        for ingredient in recipe.ingredients {
+            let pieces = ingredient.packages_required(db).await?;
+            lines.push(ExportLine {
+                item: ingredient.item(),
+                name: ingredient.name,
+                quantity: Some(ingredient.quantity).filter(|q| *q > 0.0),
+                unit: Some(ingredient.unit),
+                pieces: pieces.max(0) as usize,
+                checked: false,
+            });
+        }
+
+        Ok(Self {
+            title: recipe
+                .summary
+                .title
+                .unwrap_or_else(|| DEFAULT_TITLE.to_string()),
+            lines,
+        })
+    }
+
+    /// Total of the lines not checked off yet, like `open_price_total` of shopping lists.
+    pub fn price_total_string(&self) -> String {
+        let total: f32 = self
+            .lines
+            .iter()
+            .filter(|l| !l.checked)
+            .filter_map(|l| l.item.as_ref().map(|i| i.price_total(l.pieces)))
+            .sum();
+        format!("{:.2}", total)
+    }
+
+    /// File name for `Content-Disposition`, ascii only.
+    pub fn filename(&self, format: ExportFormat) -> String {
+        let slug = normalize(&self.title)
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        let slug = if slug.is_empty() {
+            normalize(DEFAULT_TITLE)
+        } else {
+            slug
+        };
+        format!("{slug}.{}", format.extension())
+    }
+
+    pub fn render(&self, format: ExportFormat) -> Result<String, Error> {
+        match format {
+            ExportFormat::Markdown => Ok(self.markdown()),
+            ExportFormat::Csv => self.csv(),
+            ExportFormat::Text => Ok(self.text()),
+            ExportFormat::JsonLd => Ok(self.json_ld()),
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.title);
+        for line in &self.lines {
+            let check = if line.checked { "x" } else { " " };
+            out.push_str(&format!("- [{check}] {}", line.label()));
+            if let Some(item) = &line.item {
+                let name = match &item.url {
+                    Some(url) => format!("[{}]({url})", item.name),
+                    None => item.name.clone(),
+                };
+                out.push_str(&format!(
+                    " – {} × {name}, {} €",
+                    line.pieces,
+                    item.price_total_string(line.pieces)
+                ));
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!("\n**Summe: {} €**\n", self.price_total_string()));
+        out
+    }
+
+    fn csv(&self) -> Result<String, Error> {
+        // text is quoted, so spreadsheets splitting at `;` keep the columns
+        let mut writer = csv::WriterBuilder::new()
+            .quote_style(csv::QuoteStyle::NonNumeric)
+            .from_writer(vec![]);
+        let mut write = |record: &[String]| {
+            writer.write_record(record).map_err(|e| {
+                error!("failed to write csv: {e}");
+                Error::InternalServer
+            })
+        };
+
+        write(&["name", "quantity", "unit", "item", "pieces", "price", "url"].map(String::from))?;
+        for line in &self.lines {
+            write(&[
+                line.name.clone(),
+                line.quantity.map(format_quantity).unwrap_or_default(),
+                line.unit.map(|u| u.to_string()).unwrap_or_default(),
+                line.item
+                    .as_ref()
+                    .map(|i| i.name.clone())
+                    .unwrap_or_default(),
+                line.item
+                    .as_ref()
+                    .map(|_| line.pieces.to_string())
+                    .unwrap_or_default(),
+                line.price_total_string().unwrap_or_default(),
+                line.item
+                    .as_ref()
+                    .and_then(|i| i.url.clone())
+                    .unwrap_or_default(),
+            ])?;
+        }
+
+        let bytes = writer.into_inner().map_err(|e| {
+            error!("failed to write csv: {e}");
+            Error::InternalServer
+        })?;
+        String::from_utf8(bytes).map_err(|e| {
+            error!("csv is not utf-8: {e}");
+            Error::InternalServer
+        })
+    }
+
+    /// Compact enough to paste into a chat, checked lines are left out.
+    fn text(&self) -> String {
+        let mut out = format!("{}\n", self.title);
+        for line in self.lines.iter().filter(|l| !l.checked) {
+            out.push_str(&format!("• {}", line.label()));
+            if let Some(item) = &line.item {
+                out.push_str(&format!(" ({}× {})", line.pieces, item.name));
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!("Summe: {} €\n", self.price_total_string()));
+        out
+    }
+
+    /// schema.org `ItemList`, the matched items as `Product` with their offer.
+    fn json_ld(&self) -> String {
+        let elements = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let mut element = serde_json::json!({
+                    "@type": "ListItem",
+                    "position": i + 1,
+                    "name": line.label(),
+                });
+                if let Some(item) = &line.item {
+                    element["item"] = serde_json::json!({
+                        "@type": "Product",
+                        "name": item.name,
+                        "url": item.url,
+                        "image": item.image_url,
+                        "brand": item.brand,
+                        "offers": {
+                            "@type": "Offer",
+                            "price": item.price_total_string(line.pieces),
+                            "priceCurrency": "EUR",
+                            "eligibleQuantity": {
+                                "@type": "QuantitativeValue",
+                                "value": line.pieces,
+                            },
+                        },
+                    });
+                }
+                element
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "@context": "https://schema.org",
+            "@type": "ItemList",
+            "name": self.title,
+            "numberOfItems": self.lines.len(),
+            "itemListElement": elements,
+        })
+        .to_string()
+    }
+}
+
+/// `2` instead of `2.0`, at most two decimals otherwise.
+fn format_quantity(quantity: f64) -> String {
+    if quantity.fract() == 0.0 {
+        format!("{}", quantity as i64)
+    } else {
+        format!("{:.2}", quantity)
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> Export {
+        let item = |name: &str, price_cent: i64| Item {
+            id: name.to_lowercase(),
+            name: name.to_string(),
+            price_cent: Some(price_cent),
+            url: Some(format!("https://shop.example/{}", name.to_lowercase())),
+            ..Default::default()
+        };
+        Export {
+            title: "Grillabend am Sä!".to_string(),
+            lines: vec![
+                ExportLine {
+                    name: "Tomaten; passiert".to_string(),
+                    quantity: Some(500.0),
+                    unit: Some(Unit::Gram),
+                    item: Some(item("Passata", 129)),
+                    pieces: 1,
+                    checked: false,
+                },
+                ExportLine {
+                    name: "Milch".to_string(),
+                    quantity: Some(0.5),
+                    unit: Some(Unit::Liter),
+                    item: Some(item("\"Bio\" Milch", 119)),
+                    pieces: 2,
+                    checked: true,
+                },
+                ExportLine {
+                    name: "Salz".to_string(),
+                    quantity: None,
+                    unit: None,
+                    item: None,
+                    pieces: 0,
+                    checked: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn totals_leave_out_checked_lines() {
+        let export = export();
+        assert_eq!(export.price_total_string(), "1.29");
+        assert!(export.markdown().ends_with("**Summe: 1.29 €**\n"));
+        assert!(export.text().ends_with("Summe: 1.29 €\n"));
+        assert!(!export.text().contains("Milch"));
+    }
+
+    #[test]
+    fn csv_has_header_and_quotes_text() {
+        let csv = export().csv().unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some(r#""name","quantity","unit","item","pieces","price","url""#)
+        );
+        assert_eq!(
+            lines.next(),
+            Some(r#""Tomaten; passiert",500,"g","Passata",1,1.29,"https://shop.example/passata""#)
+        );
+        assert_eq!(
+            lines.next(),
+            Some(r#""Milch",0.5,"l","""Bio"" Milch",2,2.38,"https://shop.example/""bio"" milch""#)
+        );
+        assert_eq!(lines.next(), Some(r#""Salz","","","","","","""#));
+    }
+
+    #[test]
+    fn json_ld_is_an_item_list_of_products() {
+        let json: serde_json::Value = serde_json::from_str(&export().json_ld()).unwrap();
+        assert_eq!(json["@context"], "https://schema.org");
+        assert_eq!(json["@type"], "ItemList");
+        assert_eq!(json["name"], "Grillabend am Sä!");
+        assert_eq!(json["numberOfItems"], 3);
+
+        let first = &json["itemListElement"][0];
+        assert_eq!(first["@type"], "ListItem");
+        assert_eq!(first["position"], 1);
+        assert_eq!(first["name"], "500 g Tomaten; passiert");
+        assert_eq!(first["item"]["@type"], "Product");
+        assert_eq!(first["item"]["offers"]["price"], "1.29");
+        assert_eq!(first["item"]["offers"]["priceCurrency"], "EUR");
+        assert_eq!(first["item"]["offers"]["eligibleQuantity"]["value"], 1);
+
+        let unmatched = &json["itemListElement"][2];
+        assert_eq!(unmatched["position"], 3);
+        assert!(unmatched.get("item").is_none());
+    }
+
+    #[test]
+    fn filename_is_an_ascii_slug() {
+        let mut export = export();
+        assert_eq!(
+            export.filename(ExportFormat::Markdown),
+            "grillabend-am-sa.md"
+        );
+
+        export.title = "!!!".to_string();
+        assert_eq!(
+            export.filename(ExportFormat::JsonLd),
+            "einkaufsliste.jsonld"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use super::ingredient_mapping::{normalize, DEFAULT_LOCALE};
 use super::item::Item;
 use crate::prelude::*;
 
@@ -7,17 +8,60 @@ pub struct Ingredient {
     pub id: String,
     pub name: String,
     pub probably_at_home: bool,
-    pub unit: String,
+    #[serde(default)]
+    pub at_home_probability: Option<f64>, // per user, see `Pantry::reconcile`
+    pub unit: Unit,
     pub quantity: f64,
+    #[serde(default)]
+    pub remaining_quantity: Option<f64>, // still to buy if part of `quantity` is in the pantry
+    #[serde(default)]
+    pub source_line: Option<String>, // line of the recipe the ingredient was extracted from
 
     item: Option<Item>,
     #[serde(default)]
     pub item_quantity: i64,
     #[serde(default)]
     pub alternatives: Vec<Item>,
+    #[serde(default)]
+    pub search_query: Option<SearchQuery>,
+    #[serde(default)]
+    pub aisle: Option<Aisle>,
+    #[serde(default)]
+    pub diet_conflicts: Vec<String>, // e.g. `vegan` or `disliked`
+    #[serde(default)]
+    pub substitute: Option<String>, // suggested for diet conflicts
+    #[serde(default)]
+    pub substitute_for: Option<String>, // original name once the substitute is used
+    #[serde(default)]
+    pub stale: bool, // vendor was unavailable, items are from `observed_at`
+    #[serde(default)]
+    pub observed_at: Option<DateTime<Utc>>,
 }
 
 impl Ingredient {
+    pub fn new(name: String, quantity: f64, unit: Unit) -> Self {
+        Self {
+            id: new_id(),
+            name,
+            probably_at_home: false,
+            at_home_probability: None,
+            unit,
+            quantity,
+            remaining_quantity: None,
+            source_line: None,
+            item: None,
+            item_quantity: 0,
+            alternatives: vec![],
+            search_query: None,
+            aisle: None,
+            diet_conflicts: vec![],
+            substitute: None,
+            substitute_for: None,
+            stale: false,
+            observed_at: None,
+        }
+    }
+
     pub fn item(&self) -> Option<Item> {
         self.item.clone()
     }
@@ -29,20 +73,52 @@ impl Ingredient {
         }
     }
 
-    pub fn enrich(&mut self) {
-        self.name = INGREDIENT_NAME_MAPPINGS
-            .iter()
-            .find(|(n, _)| n == &self.name)
-            .map(|(_, m)| m.to_string())
-            .unwrap_or_else(|| self.name.clone());
+    /// Quantity to buy, the recipe's quantity unless part of it is in the pantry.
+    pub fn quantity_to_buy(&self) -> f64 {
+        self.remaining_quantity.unwrap_or(self.quantity)
     }
-}
 
-const INGREDIENT_NAME_MAPPINGS: &[(&str, &str)] = &[
-    ("Mehl", "Weizenmehl"),
-    ("Zucker", "Zucker"),
-    ("Salz", "Speisesalz"),
-    ("Ei", "Eier"),
-    ("Eiweiß", "Eier"),
-    ("Eigelb", "Eier"),
-];
+    /// Key of the canonical ingredient record, the normalized name.
+    pub fn key(&self) -> String {
+        normalize(&self.name)
+    }
+
+    /// Searches the substitute instead of the conflicting ingredient.
+    pub fn use_substitute(&mut self) {
+        if self.diet_conflicts.is_empty() {
+            return;
+        }
+        if let Some(substitute) = self.substitute.take() {
+            self.substitute_for = Some(std::mem::replace(&mut self.name, substitute));
+        }
+    }
+
+    /// Replaces the name with the search term of its mapping, e.g. `Weizenmehl` for `Mehl`.
+    pub async fn enrich(&mut self, db: &Surreal<Any>) -> Result<(), Error> {
+        if let Some(mapping) = IngredientMapping::find(db, &self.name, DEFAULT_LOCALE).await? {
+            self.name = mapping.search_term();
+        }
+        Ok(())
+    }
+
+    /// Other names of the ingredient, e.g. `Ei` for `Eier`.
+    pub async fn synonyms(&self, db: &Surreal<Any>) -> Result<Vec<String>, Error> {
+        let Some(mapping) = IngredientMapping::find(db, &self.name, DEFAULT_LOCALE).await? else {
+            return Ok(vec![]);
+        };
+
+        let name = normalize(&self.name);
+        let synonyms = mapping
+            .names()
+            .into_iter()
+            .filter(|n| normalize(n) != name)
+            .fold(vec![], |mut synonyms: Vec<String>, n| {
+                if !synonyms.contains(&n) {
+                    synonyms.push(n);
+                }
+                synonyms
+            });
+
+        Ok(synonyms)
+    }
+}
@@ -0,0 +1,186 @@
+use crate::prelude::*;
+
+pub const DEFAULT_LOCALE: &str = "de";
+
+/// Maps names and aliases of an ingredient to its canonical name and the term to search vendors with.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IngredientMapping {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub canonical_name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    #[serde(default)]
+    pub search_term: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct IngredientMappingDb {
+    #[serde(default, skip_serializing)]
+    id: Option<RecordId>,
+    canonical_name: String,
+    aliases: Vec<String>,
+    locale: String,
+    search_term: Option<String>,
+    keys: Vec<String>,
+}
+
+impl From<IngredientMappingDb> for IngredientMapping {
+    fn from(mapping: IngredientMappingDb) -> Self {
+        Self {
+            id: mapping.id.map(|id| id.key().to_string()),
+            canonical_name: mapping.canonical_name,
+            aliases: mapping.aliases,
+            locale: mapping.locale,
+            search_term: mapping.search_term,
+        }
+    }
+}
+
+impl From<IngredientMapping> for IngredientMappingDb {
+    fn from(mapping: IngredientMapping) -> Self {
+        let keys = mapping.names().iter().map(|n| normalize(n)).fold(
+            vec![],
+            |mut keys: Vec<String>, key| {
+                if !key.is_empty() && !keys.contains(&key) {
+                    keys.push(key);
+                }
+                keys
+            },
+        );
+
+        Self {
+            id: None,
+            canonical_name: mapping.canonical_name.trim().to_string(),
+            aliases: mapping
+                .aliases
+                .iter()
+                .map(|a| a.trim().to_string())
+                .collect(),
+            locale: mapping.locale,
+            search_term: mapping.search_term.filter(|t| !t.trim().is_empty()),
+            keys,
+        }
+    }
+}
+
+impl IngredientMapping {
+    /// Canonical name, aliases and search term.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![self.canonical_name.clone()];
+        names.extend(self.aliases.iter().cloned());
+        names.extend(self.search_term.iter().cloned());
+        names
+    }
+
+    /// Name to search vendors with.
+    pub fn search_term(&self) -> String {
+        self.search_term
+            .clone()
+            .unwrap_or_else(|| self.canonical_name.clone())
+    }
+
+    /// Finds the mapping whose canonical name, alias or search term equals `name`,
+    /// ignoring case and diacritics.
+    pub async fn find(
+        db: &Surreal<Any>,
+        name: &str,
+        locale: &str,
+    ) -> Result<Option<IngredientMapping>, Error> {
+        let mut mappings: Vec<IngredientMappingDb> = db
+            .query("select * from ingredient_mapping where keys contains $key and locale = $locale limit 1")
+            .bind(("key", normalize(name)))
+            .bind(("locale", locale.to_string()))
+            .await?
+            .take(0)?;
+
+        Ok(mappings.pop().map(IngredientMapping::from))
+    }
+
+    pub async fn get(db: &Surreal<Any>, id: &str) -> Result<Option<IngredientMapping>, Error> {
+        let mapping: Option<IngredientMappingDb> = db.select(("ingredient_mapping", id)).await?;
+        Ok(mapping.map(IngredientMapping::from))
+    }
+
+    pub async fn all(db: &Surreal<Any>) -> Result<Vec<IngredientMapping>, Error> {
+        let mappings: Vec<IngredientMappingDb> = db
+            .query("select * from ingredient_mapping order by locale, canonical_name")
+            .await?
+            .take(0)?;
+
+        Ok(mappings.into_iter().map(IngredientMapping::from).collect())
+    }
+
+    /// Creates the mapping, or updates it if `id` is set.
+    pub async fn save(self, db: &Surreal<Any>) -> Result<IngredientMapping, Error> {
+        if self.canonical_name.trim().is_empty() {
+            return Err(Error::BadRequest("canonical name is required".to_string()));
+        }
+
+        let id = self.id.clone().unwrap_or_else(new_id);
+        let mapping: IngredientMappingDb = self.into();
+
+        // a name must not map to two ingredients
+
+        let conflicts: Vec<IngredientMappingDb> = db
+            .query("select * from ingredient_mapping where keys containsany $keys and locale = $locale and id != type::thing('ingredient_mapping', $id)")
+            .bind(("keys", mapping.keys.clone()))
+            .bind(("locale", mapping.locale.clone()))
+            .bind(("id", id.clone()))
+            .await?
+            .take(0)?;
+        if let Some(conflict) = conflicts.first() {
+            return Err(Error::Conflict(format!(
+                "name already mapped to {}",
+                conflict.canonical_name
+            )));
+        }
+
+        let Some(record): Option<IngredientMappingDb> = db
+            .upsert(("ingredient_mapping", &id))
+            .content(mapping)
+            .await?
+        else {
+            error!("failed to store ingredient mapping");
+            return Err(Error::InternalServer);
+        };
+
+        Ok(record.into())
+    }
+
+    pub async fn delete(db: &Surreal<Any>, id: &str) -> Result<(), Error> {
+        let Some(_record): Option<IngredientMappingDb> =
+            db.delete(("ingredient_mapping", id)).await?
+        else {
+            return Err(Error::NotFound);
+        };
+
+        Ok(())
+    }
+}
+
+fn default_locale() -> String {
+    DEFAULT_LOCALE.to_string()
+}
+
+/// Lowercase name without diacritics, e.g. `eiweiss` for `Eiweiß`.
+pub fn normalize(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .flat_map(|c| match c {
+            'ß' => vec!['s', 's'],
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => vec!['a'],
+            'ç' => vec!['c'],
+            'è' | 'é' | 'ê' | 'ë' => vec!['e'],
+            'ì' | 'í' | 'î' | 'ï' => vec!['i'],
+            'ñ' => vec!['n'],
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => vec!['o'],
+            'ù' | 'ú' | 'û' | 'ü' => vec!['u'],
+            'ý' | 'ÿ' => vec!['y'],
+            c => vec![c],
+        })
+        .collect()
+}
@@ -1,5 +1,17 @@
 use crate::prelude::*;
 
+/// Discounted price of an item, `Item::price_cent` is the price everyone pays,
+/// so it only equals `price_cent` of the offer if the offer is not `loyalty_only`.
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq, Hash)]
+pub struct Offer {
+    pub price_cent: i64,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub loyalty_only: bool, // e.g. only with a customer card or app
+    pub conditions: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq, Hash)]
 pub struct Item {
     pub id: String,
@@ -8,6 +20,26 @@ pub struct Item {
     pub price_cent: Option<i64>,
     pub url: Option<String>,
     pub image_url: Option<String>,
+    #[serde(default)]
+    pub brand: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub category_path: Vec<String>,
+    #[serde(default)]
+    pub available: Option<bool>,
+    #[serde(default)]
+    pub base_price_cent: Option<i64>, // per kg, l or piece
+    #[serde(default)]
+    pub product_id: Option<String>, // id of the product at the vendor
+    #[serde(default)]
+    pub regular_price_cent: Option<i64>,
+    #[serde(default)]
+    pub offer: Option<Offer>,
+    #[serde(default)]
+    pub aisle: Option<Aisle>, // from `category_path`
+    #[serde(default)]
+    pub unverified: bool, // allergens and diets were requested, but the details are unknown
 }
 
 impl Item {
@@ -20,4 +52,32 @@ impl Item {
     pub fn price_total_string(&self, pieces: usize) -> String {
         format!("{:.2}", self.price_total(pieces))
     }
+
+    /// Amount saved compared to the regular price.
+    pub fn savings(&self, pieces: usize) -> f32 {
+        match (self.regular_price_cent, self.price_cent) {
+            (Some(regular), Some(price)) if regular > price => {
+                (regular - price) as f32 / 100.0 * pieces as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn savings_string(&self, pieces: usize) -> String {
+        format!("{:.2}", self.savings(pieces))
+    }
+
+    pub fn is_discounted(&self) -> bool {
+        self.offer.is_some() || self.savings(1) > 0.0
+    }
+
+    pub fn is_organic(&self) -> bool {
+        self.labels
+            .iter()
+            .any(|l| l.eq_ignore_ascii_case("organic") || l.eq_ignore_ascii_case("bio"))
+            || self
+                .name
+                .split_whitespace()
+                .any(|w| w.eq_ignore_ascii_case("bio"))
+    }
 }
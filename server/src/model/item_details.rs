@@ -0,0 +1,121 @@
+use crate::prelude::*;
+use surrealdb::sql::Datetime;
+
+const CACHE_DAYS: i64 = 7;
+
+/// Nutrition facts per 100 g or 100 ml.
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+pub struct Nutrition {
+    pub energy_kcal: Option<f64>,
+    pub fat: Option<f64>,
+    pub saturated_fat: Option<f64>,
+    pub carbohydrates: Option<f64>,
+    pub sugar: Option<f64>,
+    pub fibre: Option<f64>,
+    pub protein: Option<f64>,
+    pub salt: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+pub struct ItemDetails {
+    pub nutrition: Option<Nutrition>,
+    #[serde(default)]
+    pub allergens: Vec<String>,
+    pub ingredients: Option<String>,
+    pub origin: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct ItemDetailsDb {
+    vendor: String,
+    product_id: String,
+    nutrition: Option<Nutrition>,
+    allergens: Vec<String>,
+    ingredients: Option<String>,
+    origin: Option<String>,
+    labels: Vec<String>,
+    fetched_at: Datetime,
+}
+
+impl From<ItemDetailsDb> for ItemDetails {
+    fn from(details: ItemDetailsDb) -> Self {
+        Self {
+            nutrition: details.nutrition,
+            allergens: details.allergens,
+            ingredients: details.ingredients,
+            origin: details.origin,
+            labels: details.labels,
+        }
+    }
+}
+
+impl ItemDetails {
+    /// Whether one of the allergens contains one of the given ones, e.g. `Milch` in `Milch und Milcherzeugnisse`.
+    pub fn contains_allergen(&self, allergens: &[String]) -> bool {
+        self.allergens.iter().any(|a| {
+            let a = a.to_lowercase();
+            allergens.iter().any(|b| a.contains(&b.to_lowercase()))
+        })
+    }
+}
+
+impl Vendor {
+    /// Details of the item from cache, or from the vendor if not cached for `CACHE_DAYS`.
+    pub async fn item_details(
+        &self,
+        state: &AppState,
+        item: &Item,
+    ) -> Result<Option<ItemDetails>, Error> {
+        let Some(product_id) = &item.product_id else {
+            return Ok(None);
+        };
+        let key = details_key(&self.name(), product_id);
+
+        // check cache
+
+        let cached: Option<ItemDetailsDb> = state.db.select(("item_details", &key)).await?;
+        if let Some(cached) = cached {
+            if cached.fetched_at.0 > Utc::now() - Duration::days(CACHE_DAYS) {
+                return Ok(Some(cached.into()));
+            }
+        }
+
+        // ask vendor
+
+        let Some(details) = self.client(state).item_details(product_id).await? else {
+            return Ok(None);
+        };
+
+        let details_db = ItemDetailsDb {
+            vendor: self.name(),
+            product_id: product_id.clone(),
+            nutrition: details.nutrition.clone(),
+            allergens: details.allergens.clone(),
+            ingredients: details.ingredients.clone(),
+            origin: details.origin.clone(),
+            labels: details.labels.clone(),
+            fetched_at: Utc::now().into(),
+        };
+        if let Err(err) = state
+            .db
+            .upsert::<Option<ItemDetailsDb>>(("item_details", &key))
+            .content(details_db)
+            .await
+        {
+            error!("failed to cache item details: {err:?}");
+        }
+
+        Ok(Some(details))
+    }
+}
+
+fn details_key(vendor: &str, product_id: &str) -> String {
+    let product_id = product_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>();
+
+    format!("{vendor}_{product_id}")
+}
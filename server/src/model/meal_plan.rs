@@ -0,0 +1,374 @@
+use crate::prelude::*;
+use surrealdb::sql::Datetime;
+
+const MEAL_PLAN_FIELDS: &str = "id, title, created_at, (select recipe, servings, recipe.title as title, recipe.servings as recipe_servings from $parent.meals) as meals";
+
+/// Recipes planned together, e.g. the dinners of a week.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MealPlan {
+    pub id: String,
+    pub title: Option<String>,
+    pub meals: Vec<Meal>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Recipe of a meal plan. The quantities are scaled to `servings` if the recipe's are known.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Meal {
+    pub recipe_id: String,
+    #[serde(default)]
+    pub servings: Option<u32>,
+    #[serde(default)]
+    pub title: Option<String>, // of the recipe
+    #[serde(default)]
+    pub recipe_servings: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct MealPlanIn {
+    pub title: Option<String>,
+    pub meals: Vec<Meal>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MealPlanDb {
+    user: Thing,
+    title: Option<String>,
+    meals: Vec<MealDb>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MealDb {
+    recipe: Thing,
+    servings: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MealPlanRow {
+    id: Thing,
+    title: Option<String>,
+    meals: Vec<MealRow>,
+    created_at: Datetime,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MealRow {
+    recipe: Thing,
+    servings: Option<u32>,
+    title: Option<String>,
+    recipe_servings: Option<u32>,
+}
+
+impl From<MealPlanRow> for MealPlan {
+    fn from(row: MealPlanRow) -> Self {
+        Self {
+            id: row.id.id.to_raw(),
+            title: row.title,
+            meals: row
+                .meals
+                .into_iter()
+                .map(|m| Meal {
+                    recipe_id: m.recipe.id.to_raw(),
+                    servings: m.servings,
+                    title: m.title,
+                    recipe_servings: m.recipe_servings,
+                })
+                .collect(),
+            created_at: row.created_at.0,
+        }
+    }
+}
+
+impl Meal {
+    /// Factor for the quantities of the recipe, 1 unless both servings are known.
+    fn scale(&self) -> f64 {
+        match (self.servings, self.recipe_servings) {
+            (Some(servings), Some(recipe_servings)) if recipe_servings > 0 => {
+                servings as f64 / recipe_servings as f64
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+impl MealPlan {
+    pub async fn list(db: &Surreal<Any>, username: &str) -> Result<Vec<MealPlan>, Error> {
+        let rows: Vec<MealPlanRow> = db
+            .query(format!("select {MEAL_PLAN_FIELDS} from meal_plan where user = type::thing('user', $username) order by created_at desc"))
+            .bind(("username", username.to_string()))
+            .await?
+            .take(0)?;
+
+        Ok(rows.into_iter().map(MealPlan::from).collect())
+    }
+
+    pub async fn get(db: &Surreal<Any>, username: &str, id: &str) -> Result<MealPlan, Error> {
+        let mut rows: Vec<MealPlanRow> = db
+            .query(format!("select {MEAL_PLAN_FIELDS} from meal_plan where id = type::thing('meal_plan', $id) and user = type::thing('user', $username)"))
+            .bind(("username", username.to_string()))
+            .bind(("id", id.to_string()))
+            .await?
+            .take(0)?;
+
+        rows.pop().map(MealPlan::from).ok_or(Error::NotFound)
+    }
+
+    pub async fn create(
+        db: &Surreal<Any>,
+        username: &str,
+        plan: MealPlanIn,
+    ) -> Result<MealPlan, Error> {
+        let plan_db = Self::validate(db, username, plan).await?;
+        let Some(row): Option<MealPlanRow> = db
+            .query(format!(
+                "select {MEAL_PLAN_FIELDS} from (create meal_plan content $plan)"
+            ))
+            .bind(("plan", plan_db))
+            .await?
+            .take(0)?
+        else {
+            error!("failed to store meal plan");
+            return Err(Error::InternalServer);
+        };
+
+        Ok(row.into())
+    }
+
+    /// Replaces title and meals of the plan.
+    pub async fn update(
+        db: &Surreal<Any>,
+        username: &str,
+        id: &str,
+        plan: MealPlanIn,
+    ) -> Result<MealPlan, Error> {
+        Self::get(db, username, id).await?;
+        let plan_db = Self::validate(db, username, plan).await?;
+
+        db.query("update type::thing('meal_plan', $id) set title = $title, meals = $meals")
+            .bind(("id", id.to_string()))
+            .bind(("title", plan_db.title))
+            .bind(("meals", plan_db.meals))
+            .await?
+            .check()?;
+
+        Self::get(db, username, id).await
+    }
+
+    pub async fn delete(db: &Surreal<Any>, username: &str, id: &str) -> Result<(), Error> {
+        Self::get(db, username, id).await?;
+
+        db.query("delete type::thing('meal_plan', $id)")
+            .bind(("id", id.to_string()))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Distinct recipes of the plan in their order.
+    pub fn recipe_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = vec![];
+        for meal in &self.meals {
+            if !ids.contains(&meal.recipe_id) {
+                ids.push(meal.recipe_id.clone());
+            }
+        }
+        ids
+    }
+
+    /// Ingredients of all meals scaled to their servings, merged into one list.
+    pub async fn ingredients(
+        &self,
+        db: &Surreal<Any>,
+        username: &str,
+    ) -> Result<Vec<Ingredient>, Error> {
+        let mut ingredients = vec![];
+        for meal in &self.meals {
+            let recipe = RecipeHistory::get(db, username, &meal.recipe_id).await?;
+            let scale = meal.scale();
+            ingredients.extend(recipe.ingredients.into_iter().map(|mut i| {
+                i.quantity *= scale;
+                i
+            }));
+        }
+
+        Ingredient::consolidate(db, ingredients).await
+    }
+
+    async fn validate(
+        db: &Surreal<Any>,
+        username: &str,
+        plan: MealPlanIn,
+    ) -> Result<MealPlanDb, Error> {
+        let mut checked: HashSet<String> = HashSet::new();
+        for meal in &plan.meals {
+            if meal.servings == Some(0) {
+                return Err(Error::BadRequest("servings must be positive".to_string()));
+            }
+            if checked.insert(meal.recipe_id.clone()) {
+                RecipeHistory::check_owner(db, username, &meal.recipe_id).await?;
+            }
+        }
+
+        Ok(MealPlanDb {
+            user: Thing::from(("user", username)),
+            title: plan
+                .title
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty()),
+            meals: plan
+                .meals
+                .into_iter()
+                .map(|m| MealDb {
+                    recipe: Thing::from(("recipe", m.recipe_id.as_str())),
+                    servings: m.servings,
+                })
+                .collect(),
+        })
+    }
+}
+
+impl Ingredient {
+    /// Merges ingredients with the same canonical name, converting quantities to the unit of the
+    /// first one they can be converted to. Quantities that can't be converted stay separate.
+    /// Expects the ingredients as in the recipe, before the pantry is applied.
+    pub async fn merge(
+        db: &Surreal<Any>,
+        ingredients: Vec<Ingredient>,
+    ) -> Result<Vec<Ingredient>, Error> {
+        let mut merged: Vec<Ingredient> = vec![];
+        for ingredient in ingredients {
+            let key = ingredient.key();
+            let candidates = merged
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| m.key() == key)
+                .map(|(i, m)| (i, m.unit))
+                .collect::<Vec<_>>();
+
+            let mut target = None;
+            for (i, unit) in candidates {
+                if let Some(quantity) = ingredient.quantity_in(db, unit).await? {
+                    target = Some((i, quantity));
+                    break;
+                }
+            }
+
+            let Some((i, quantity)) = target else {
+                merged.push(ingredient);
+                continue;
+            };
+            let existing = &mut merged[i];
+            existing.quantity += quantity;
+            existing.probably_at_home &= ingredient.probably_at_home;
+            existing.source_line = None;
+            if existing.item().is_none() {
+                if let Some(item) = ingredient.item() {
+                    existing.alternatives = ingredient.alternatives;
+                    existing.select_item(item.id, None);
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Merged ingredients with the package counts computed once on the summed quantities.
+    pub async fn consolidate(
+        db: &Surreal<Any>,
+        ingredients: Vec<Ingredient>,
+    ) -> Result<Vec<Ingredient>, Error> {
+        let mut merged = Self::merge(db, ingredients).await?;
+        for ingredient in merged.iter_mut() {
+            ingredient.item_quantity = ingredient.packages_required(db).await?;
+        }
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn db() -> Surreal<Any> {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query("create density:mohre content { name: 'Möhre', grams_per_piece: 80.0 }")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        db
+    }
+
+    fn ingredient(name: &str, quantity: f64, unit: Unit) -> Ingredient {
+        Ingredient::new(name.to_string(), quantity, unit)
+    }
+
+    #[tokio::test]
+    async fn merges_quantities_across_units() {
+        let db = db().await;
+        let merged = Ingredient::merge(
+            &db,
+            vec![
+                ingredient("Möhre", 200.0, Unit::Gram),
+                ingredient("Mehl", 1.0, Unit::Kilogram),
+                ingredient("möhre", 2.0, Unit::Piece),
+                ingredient("Mehl", 250.0, Unit::Gram),
+                ingredient("Salz", 1.0, Unit::Pinch),
+                ingredient("Salz", 5.0, Unit::Gram),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let quantities = merged
+            .iter()
+            .map(|i| (i.name.as_str(), i.quantity, i.unit))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            quantities,
+            vec![
+                ("Möhre", 360.0, Unit::Gram),
+                ("Mehl", 1.25, Unit::Kilogram),
+                ("Salz", 1.0, Unit::Pinch),
+                ("Salz", 5.0, Unit::Gram),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn counts_packages_of_summed_quantities() {
+        let db = db().await;
+        let with_item = |quantity: f64, grammage: Option<&str>| {
+            let mut ingredient = ingredient("Möhre", quantity, Unit::Gram);
+            ingredient.alternatives = vec![Item {
+                id: "moehren".to_string(),
+                grammage: grammage.map(String::from),
+                ..Default::default()
+            }];
+            ingredient.select_item("moehren".to_string(), None);
+            ingredient
+        };
+
+        // 400 g and 5 pieces of 80 g are 800 g, two packages of 500 g
+        let consolidated = Ingredient::consolidate(
+            &db,
+            vec![
+                with_item(400.0, Some("500g")),
+                ingredient("Möhre", 5.0, Unit::Piece),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(consolidated.len(), 1);
+        assert_eq!(consolidated[0].quantity, 800.0);
+        assert_eq!(consolidated[0].item_quantity, 2);
+
+        // the package size is unknown, one is bought
+        let consolidated = Ingredient::consolidate(&db, vec![with_item(400.0, None)])
+            .await
+            .unwrap();
+        assert_eq!(consolidated[0].item_quantity, 1);
+    }
+}
@@ -1,4 +1,17 @@
+pub mod aisle;
 pub mod cash_flow;
+pub mod conversion;
+pub mod diet;
+pub mod export;
 pub mod ingredient;
+pub mod ingredient_mapping;
 pub mod item;
+pub mod item_details;
+pub mod meal_plan;
+pub mod nutrition;
+pub mod pantry;
+pub mod recipe;
+pub mod recipe_import;
+pub mod shopping_list;
+pub mod unit;
 pub mod vendor;
@@ -0,0 +1,142 @@
+use super::ingredient_mapping::{normalize, DEFAULT_LOCALE};
+use crate::prelude::*;
+
+/// Sum of a nutrient, `estimated_share` is the part of `total` from generic food composition data.
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+pub struct NutrientEstimate {
+    pub total: f64,
+    pub per_serving: f64,
+    pub estimated_share: f64,
+    #[serde(skip)]
+    estimated: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+pub struct NutritionEstimate {
+    pub servings: u32,
+    pub energy_kcal: NutrientEstimate,
+    pub protein: NutrientEstimate,
+    pub fat: NutrientEstimate,
+    pub carbohydrates: NutrientEstimate,
+    pub sugar: NutrientEstimate,
+    pub fibre: NutrientEstimate,
+    pub salt: NutrientEstimate,
+    pub missing: Vec<String>, // ingredients without weight or nutrition data
+}
+
+impl NutrientEstimate {
+    /// Adds the nutrient per 100 g of an ingredient, preferring item data over generic data.
+    fn add(&mut self, grams: f64, item: Option<f64>, generic: Option<f64>) {
+        if let Some(value) = item {
+            self.total += value * grams / 100.0;
+        } else if let Some(value) = generic {
+            self.total += value * grams / 100.0;
+            self.estimated += value * grams / 100.0;
+        }
+    }
+
+    fn finish(&mut self, servings: u32) {
+        self.per_serving = self.total / servings as f64;
+        self.estimated_share = if self.total > 0.0 {
+            self.estimated / self.total
+        } else {
+            0.0
+        };
+    }
+}
+
+impl Nutrition {
+    /// Generic food composition of the ingredient, also looked up by its synonyms.
+    pub async fn generic(
+        db: &Surreal<Any>,
+        ingredient: &Ingredient,
+    ) -> Result<Option<Nutrition>, Error> {
+        let mut keys = vec![ingredient.key()];
+        if let Some(mapping) = IngredientMapping::find(db, &ingredient.name, DEFAULT_LOCALE).await?
+        {
+            keys.extend(mapping.names().iter().map(|n| normalize(n)));
+        }
+
+        for key in keys {
+            let nutrition: Option<Nutrition> = db.select(("generic_nutrition", key)).await?;
+            if nutrition.is_some() {
+                return Ok(nutrition);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl NutritionEstimate {
+    /// Sums the nutrition of the ingredients, using the details of matched items of the vendor
+    /// where available and the generic food composition table otherwise.
+    pub async fn calculate(
+        state: &AppState,
+        vendor: &Vendor,
+        ingredients: &[Ingredient],
+        servings: u32,
+    ) -> Result<NutritionEstimate, Error> {
+        let servings = servings.max(1);
+        let mut estimate = NutritionEstimate {
+            servings,
+            ..Default::default()
+        };
+
+        for ingredient in ingredients {
+            let Some(grams) = ingredient.quantity_in(&state.db, Unit::Gram).await? else {
+                estimate.missing.push(ingredient.name.clone());
+                continue;
+            };
+
+            // an unavailable vendor leaves the generic values
+            let item = match ingredient.item() {
+                Some(item) => match vendor.item_details(state, &item).await {
+                    Ok(details) => details.and_then(|d| d.nutrition),
+                    Err(err) => {
+                        warn!(
+                            "failed to get nutrition of '{}' at {}: {err:?}",
+                            item.name,
+                            vendor.name()
+                        );
+                        None
+                    }
+                },
+                None => None,
+            };
+            let generic = Nutrition::generic(&state.db, ingredient).await?;
+            if item.is_none() && generic.is_none() {
+                estimate.missing.push(ingredient.name.clone());
+                continue;
+            }
+
+            let item = item.unwrap_or_default();
+            let generic = generic.unwrap_or_default();
+            estimate
+                .energy_kcal
+                .add(grams, item.energy_kcal, generic.energy_kcal);
+            estimate.protein.add(grams, item.protein, generic.protein);
+            estimate.fat.add(grams, item.fat, generic.fat);
+            estimate
+                .carbohydrates
+                .add(grams, item.carbohydrates, generic.carbohydrates);
+            estimate.sugar.add(grams, item.sugar, generic.sugar);
+            estimate.fibre.add(grams, item.fibre, generic.fibre);
+            estimate.salt.add(grams, item.salt, generic.salt);
+        }
+
+        for nutrient in [
+            &mut estimate.energy_kcal,
+            &mut estimate.protein,
+            &mut estimate.fat,
+            &mut estimate.carbohydrates,
+            &mut estimate.sugar,
+            &mut estimate.fibre,
+            &mut estimate.salt,
+        ] {
+            nutrient.finish(servings);
+        }
+
+        Ok(estimate)
+    }
+}
@@ -0,0 +1,233 @@
+use super::ingredient_mapping::{normalize, DEFAULT_LOCALE};
+use crate::prelude::*;
+
+/// Weight of the ai's guess against the user's own removals, in removals.
+const PRIOR_WEIGHT: f64 = 1.0;
+
+/// Something a user has at home, without quantity if the user always has enough of it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PantryItem {
+    #[serde(default)]
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub quantity: Option<f64>,
+    #[serde(default)]
+    pub unit: Option<Unit>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct PantryItemDb {
+    user: Thing,
+    name: String,
+    key: String,
+    quantity: Option<f64>,
+    unit: Option<Unit>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AtHomeStat {
+    key: String,
+    shown: i64,
+    removed: i64,
+}
+
+pub struct Pantry;
+
+impl Pantry {
+    pub async fn items(db: &Surreal<Any>, username: &str) -> Result<Vec<PantryItem>, Error> {
+        let items: Vec<PantryItem> = db
+            .query("select name, key, quantity, unit from pantry_item where user = type::thing('user', $username) order by name")
+            .bind(("username", username.to_string()))
+            .await?
+            .take(0)?;
+        Ok(items)
+    }
+
+    /// Adds the item or replaces the quantity of the item with the same name.
+    pub async fn add(
+        db: &Surreal<Any>,
+        username: &str,
+        item: PantryItem,
+    ) -> Result<PantryItem, Error> {
+        let name = item.name.trim().to_string();
+        let key = normalize(&name);
+        if key.is_empty() {
+            return Err(Error::BadRequest("name is required".to_string()));
+        }
+        if item.quantity.is_some() != item.unit.is_some() {
+            return Err(Error::BadRequest(
+                "quantity and unit are only allowed together".to_string(),
+            ));
+        }
+
+        let item_db = PantryItemDb {
+            user: thing(&format!("user:{username}"))?,
+            name: name.clone(),
+            key: key.clone(),
+            quantity: item.quantity,
+            unit: item.unit,
+        };
+        let stored: Option<PantryItemDb> = db
+            .query("upsert type::thing('pantry_item', [$username, $key]) content $item")
+            .bind(("username", username.to_string()))
+            .bind(("key", key.clone()))
+            .bind(("item", item_db))
+            .await?
+            .take(0)?;
+        if stored.is_none() {
+            error!("failed to store pantry item");
+            return Err(Error::InternalServer);
+        }
+
+        Ok(PantryItem {
+            key,
+            name,
+            quantity: item.quantity,
+            unit: item.unit,
+        })
+    }
+
+    pub async fn remove(db: &Surreal<Any>, username: &str, key: &str) -> Result<(), Error> {
+        let deleted: Option<PantryItemDb> = db
+            .query("delete type::thing('pantry_item', [$username, $key]) return before")
+            .bind(("username", username.to_string()))
+            .bind(("key", key.to_string()))
+            .await?
+            .take(0)?;
+        if deleted.is_none() {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Learns that the user removed the ingredient from the shopping list, because it is at home.
+    pub async fn record_removal(
+        db: &Surreal<Any>,
+        username: &str,
+        name: &str,
+    ) -> Result<(), Error> {
+        db.query("upsert type::thing('at_home_stat', [$username, $key]) set user = type::thing('user', $username), key = $key, removed += 1")
+            .bind(("username", username.to_string()))
+            .bind(("key", normalize(name)))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    /// Decides per user whether the ingredients are at home. Items in the pantry override the ai's
+    /// guess, partially available quantities leave a `remaining_quantity` to buy. Otherwise the guess
+    /// is weighed against how often the user removed the ingredient from the list of all the times
+    /// it came up.
+    pub async fn reconcile(
+        db: &Surreal<Any>,
+        username: &str,
+        ingredients: &mut [Ingredient],
+    ) -> Result<(), Error> {
+        let pantry = Self::items(db, username).await?;
+        let stats: Vec<AtHomeStat> = db
+            .query("select key, shown, removed from at_home_stat where user = type::thing('user', $username)")
+            .bind(("username", username.to_string()))
+            .await?
+            .take(0)?;
+
+        let mut shown: Vec<String> = vec![];
+        for ingredient in ingredients.iter_mut() {
+            let keys = Self::keys(db, ingredient).await?;
+
+            // pantry
+
+            if Self::stock(db, &pantry, &keys, ingredient).await? {
+                continue;
+            }
+
+            // learned probability
+
+            let prior = if ingredient.probably_at_home {
+                1.0
+            } else {
+                0.0
+            };
+            let (n, removed) = stats
+                .iter()
+                .find(|s| keys.contains(&s.key))
+                .map(|s| (s.shown as f64, s.removed as f64))
+                .unwrap_or_default();
+            let probability =
+                ((removed + prior * PRIOR_WEIGHT) / (n + PRIOR_WEIGHT)).clamp(0.0, 1.0);
+
+            ingredient.at_home_probability = Some(probability);
+            ingredient.probably_at_home = probability >= 0.5;
+
+            // every occurrence counts, otherwise the estimate could not recover once it drops below 0.5
+            shown.push(normalize(&ingredient.name));
+        }
+
+        for key in shown {
+            db.query("upsert type::thing('at_home_stat', [$username, $key]) set user = type::thing('user', $username), key = $key, shown += 1")
+                .bind(("username", username.to_string()))
+                .bind(("key", key))
+                .await?
+                .check()?;
+        }
+
+        Ok(())
+    }
+
+    /// Normalized name and synonyms of the ingredient.
+    async fn keys(db: &Surreal<Any>, ingredient: &Ingredient) -> Result<Vec<String>, Error> {
+        let mut keys = vec![normalize(&ingredient.name)];
+        if let Some(mapping) = IngredientMapping::find(db, &ingredient.name, DEFAULT_LOCALE).await?
+        {
+            keys.extend(mapping.names().iter().map(|n| normalize(n)));
+        }
+        Ok(keys)
+    }
+
+    /// Applies the pantry to ingredients stored before, e.g. of a recipe put on a shopping list.
+    pub async fn subtract(
+        db: &Surreal<Any>,
+        username: &str,
+        ingredients: &mut [Ingredient],
+    ) -> Result<(), Error> {
+        let pantry = Self::items(db, username).await?;
+        if pantry.is_empty() {
+            return Ok(());
+        }
+        for ingredient in ingredients.iter_mut() {
+            let keys = Self::keys(db, ingredient).await?;
+            Self::stock(db, &pantry, &keys, ingredient).await?;
+        }
+        Ok(())
+    }
+
+    /// Marks the ingredient at home if it is in the pantry, with the quantity still to buy if the
+    /// stock is not enough. Returns `false` if it is not in the pantry.
+    async fn stock(
+        db: &Surreal<Any>,
+        pantry: &[PantryItem],
+        keys: &[String],
+        ingredient: &mut Ingredient,
+    ) -> Result<bool, Error> {
+        let Some(item) = pantry.iter().find(|p| keys.contains(&p.key)) else {
+            return Ok(false);
+        };
+        ingredient.at_home_probability = Some(1.0);
+        ingredient.probably_at_home = true;
+        ingredient.remaining_quantity = None;
+
+        let available = match (item.quantity, item.unit) {
+            (Some(quantity), Some(unit)) => {
+                ingredient
+                    .convert_quantity(db, quantity, unit, ingredient.unit)
+                    .await?
+            }
+            _ => None,
+        };
+        if let Some(available) = available.filter(|a| *a < ingredient.quantity) {
+            ingredient.remaining_quantity = Some(ingredient.quantity - available);
+            ingredient.probably_at_home = false;
+        }
+        Ok(true)
+    }
+}
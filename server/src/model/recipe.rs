@@ -0,0 +1,260 @@
+use crate::prelude::*;
+use surrealdb::sql::Datetime;
+
+const MAX_LIMIT: usize = 100;
+
+/// Recipe as listed in the history of a user.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RecipeSummary {
+    pub id: String,
+    pub title: Option<String>,
+    pub text: String,
+    pub servings: Option<u32>,
+    pub source_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Recipe with its extracted ingredients and the items matched for the user.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RecipeDetail {
+    #[serde(flatten)]
+    pub summary: RecipeSummary,
+    pub notes: Option<String>,
+    pub ingredients: Vec<Ingredient>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct RecipeUpdate {
+    pub title: Option<String>,
+    pub notes: Option<String>,
+    pub servings: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RecipeRow {
+    id: Thing,
+    title: Option<String>,
+    text: String,
+    notes: Option<String>,
+    servings: Option<u32>,
+    source_url: Option<String>,
+    created_at: Datetime,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RequiresRow {
+    name: String,
+    quantity: f64,
+    unit: Unit,
+    probably_at_home: bool,
+    source_line: Option<String>,
+    r#match: Option<MatchRow>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MatchRow {
+    item_id: Thing,
+    item: ItemDb,
+}
+
+impl From<RecipeRow> for RecipeSummary {
+    fn from(row: RecipeRow) -> Self {
+        Self {
+            id: row.id.id.to_raw(),
+            title: row.title,
+            text: row.text,
+            servings: row.servings,
+            source_url: row.source_url,
+            created_at: row.created_at.0,
+        }
+    }
+}
+
+pub struct RecipeHistory;
+
+impl RecipeHistory {
+    /// Recipes submitted by the user, latest first.
+    pub async fn list(
+        db: &Surreal<Any>,
+        username: &str,
+        page: usize,
+        limit: usize,
+    ) -> Result<Vec<RecipeSummary>, Error> {
+        let limit = limit.clamp(1, MAX_LIMIT);
+        let start = (page.max(1) - 1) * limit;
+
+        let rows: Vec<RecipeRow> = db
+            .query(
+                r#"
+                    select
+                        out as id,
+                        out.title as title,
+                        out.text as text,
+                        out.notes as notes,
+                        out.servings as servings,
+                        out.source_url as source_url,
+                        created_at
+                    from
+                        submits
+                    where
+                        in = type::thing('user', $username)
+                    order by
+                        created_at desc
+                    limit $limit
+                    start $start
+                "#,
+            )
+            .bind(("username", username.to_string()))
+            .bind(("limit", limit))
+            .bind(("start", start))
+            .await?
+            .take(0)?;
+
+        Ok(rows.into_iter().map(RecipeSummary::from).collect())
+    }
+
+    pub async fn get(db: &Surreal<Any>, username: &str, id: &str) -> Result<RecipeDetail, Error> {
+        let row = Self::row(db, username, id).await?;
+
+        let requires: Vec<RequiresRow> = db
+            .query(
+                r#"
+                    select
+                        out.name as name,
+                        quantity,
+                        unit,
+                        probably_at_home,
+                        source_line,
+                        (
+                            select in as item_id, in.* as item, created_at from matches
+                            where out = $parent.out and user = type::thing('user', $username)
+                            order by created_at desc limit 1
+                        )[0] as match
+                    from
+                        requires
+                    where
+                        in = type::thing('recipe', $id)
+                "#,
+            )
+            .bind(("username", username.to_string()))
+            .bind(("id", id.to_string()))
+            .await?
+            .take(0)?;
+
+        let ingredients = requires
+            .into_iter()
+            .map(|r| {
+                let mut ingredient = Ingredient::new(r.name, r.quantity, r.unit);
+                ingredient.probably_at_home = r.probably_at_home;
+                ingredient.source_line = r.source_line;
+                if let Some(m) = r.r#match {
+                    let id = m.item_id.id.to_raw();
+                    ingredient.alternatives = vec![m.item.into_item(id.clone())];
+                    ingredient.select_item(id, None);
+                }
+                ingredient
+            })
+            .collect();
+
+        Ok(RecipeDetail {
+            notes: row.notes.clone(),
+            summary: row.into(),
+            ingredients,
+        })
+    }
+
+    pub async fn update(
+        db: &Surreal<Any>,
+        username: &str,
+        id: &str,
+        update: RecipeUpdate,
+    ) -> Result<RecipeDetail, Error> {
+        Self::row(db, username, id).await?;
+
+        // empty strings clear the field
+        let clean = |value: Option<String>| value.map(|v| v.trim().to_string());
+        let mut query = vec![];
+        if let Some(title) = clean(update.title.clone()) {
+            query.push(if title.is_empty() {
+                "title = none"
+            } else {
+                "title = $title"
+            });
+        }
+        if let Some(notes) = clean(update.notes.clone()) {
+            query.push(if notes.is_empty() {
+                "notes = none"
+            } else {
+                "notes = $notes"
+            });
+        }
+
+        if let Some(servings) = update.servings {
+            if servings == 0 {
+                return Err(Error::BadRequest("servings must be positive".to_string()));
+            }
+            query.push("servings = $servings");
+        }
+
+        if !query.is_empty() {
+            db.query(format!(
+                "update type::thing('recipe', $id) set {}",
+                query.join(", ")
+            ))
+            .bind(("id", id.to_string()))
+            .bind(("title", clean(update.title)))
+            .bind(("notes", clean(update.notes)))
+            .bind(("servings", update.servings))
+            .await?
+            .check()?;
+        }
+
+        Self::get(db, username, id).await
+    }
+
+    /// Deletes the recipe, its `submits` and `requires` relations go with it.
+    /// Canonical ingredients and items are shared and kept.
+    pub async fn delete(db: &Surreal<Any>, username: &str, id: &str) -> Result<(), Error> {
+        Self::row(db, username, id).await?;
+
+        db.query("delete type::thing('recipe', $id)")
+            .bind(("id", id.to_string()))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Fails with `NotFound` unless the user submitted the recipe.
+    pub async fn check_owner(db: &Surreal<Any>, username: &str, id: &str) -> Result<(), Error> {
+        Self::row(db, username, id).await.map(|_| ())
+    }
+
+    /// The recipe if the user submitted it.
+    async fn row(db: &Surreal<Any>, username: &str, id: &str) -> Result<RecipeRow, Error> {
+        let mut rows: Vec<RecipeRow> = db
+            .query(
+                r#"
+                    select
+                        out as id,
+                        out.title as title,
+                        out.text as text,
+                        out.notes as notes,
+                        out.servings as servings,
+                        out.source_url as source_url,
+                        created_at
+                    from
+                        submits
+                    where
+                        in = type::thing('user', $username)
+                        and out = type::thing('recipe', $id)
+                "#,
+            )
+            .bind(("username", username.to_string()))
+            .bind(("id", id.to_string()))
+            .await?
+            .take(0)?;
+
+        rows.pop().ok_or(Error::NotFound)
+    }
+}
@@ -0,0 +1,470 @@
+use super::vendor::http::USER_AGENT_VALUE;
+use crate::prelude::*;
+use regex::Regex;
+use serde_json::Value;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::LazyLock;
+use tokio::time::{timeout, Duration as StdDuration};
+
+const MAX_REDIRECTS: usize = 3;
+
+/// Content of `application/ld+json` scripts.
+static JSON_LD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<script[^>]*type\s*=\s*["']?application/ld\+json["']?[^>]*>(.*?)</script>"#)
+        .expect("invalid json-ld regex")
+});
+
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").expect("invalid tag regex"));
+
+/// Recipe read from the schema.org `Recipe` JSON-LD most recipe sites embed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ImportedRecipe {
+    pub title: Option<String>,
+    pub servings: Option<u32>,
+    pub ingredients: Vec<String>, // `recipeIngredient`, one line per ingredient
+    pub instructions: Vec<String>,
+    pub source_url: Option<String>,
+}
+
+impl ImportedRecipe {
+    /// Fetches the page, public addresses only and within size and time limits.
+    pub async fn fetch(url: &str) -> Result<Self, Error> {
+        let seconds = env_number("RECIPE_IMPORT_TIMEOUT_SECONDS", 10);
+        let Ok(html) = timeout(StdDuration::from_secs(seconds), fetch_html(url)).await else {
+            warn!("⏱️ fetching {url} took longer than {seconds}s");
+            return Err(Error::BadRequest(format!("{url} did not respond in time")));
+        };
+        Self::from_html(&html?, Some(url.to_string()))
+    }
+
+    pub fn from_html(html: &str, source_url: Option<String>) -> Result<Self, Error> {
+        if let Some(url) = &source_url {
+            reqwest::Url::parse(url)
+                .map_err(|_| Error::BadRequest(format!("invalid url {url}")))?;
+        }
+
+        let Some(recipe) = json_ld(html).iter().find_map(find_recipe).cloned() else {
+            return Err(Error::BadRequest(
+                "no schema.org recipe found in page".to_string(),
+            ));
+        };
+
+        let ingredients = strings(
+            recipe
+                .get("recipeIngredient")
+                .or_else(|| recipe.get("ingredients")),
+        );
+        if ingredients.is_empty() {
+            return Err(Error::BadRequest("recipe has no ingredients".to_string()));
+        }
+
+        let mut instructions = vec![];
+        if let Some(value) = recipe.get("recipeInstructions") {
+            collect_instructions(value, &mut instructions);
+        }
+
+        Ok(Self {
+            title: recipe
+                .get("name")
+                .and_then(Value::as_str)
+                .map(clean)
+                .filter(|t| !t.is_empty()),
+            servings: recipe.get("recipeYield").and_then(servings),
+            ingredients,
+            instructions,
+            source_url,
+        })
+    }
+
+    /// Text stored as the recipe, ingredients followed by the instructions.
+    pub fn text(&self) -> String {
+        let mut text = self.ingredient_text();
+        if !self.instructions.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(&self.instructions.join("\n"));
+        }
+        text
+    }
+
+    /// What the ingredients are extracted from, one line per ingredient.
+    pub fn ingredient_text(&self) -> String {
+        self.ingredients.join("\n")
+    }
+}
+
+async fn fetch_html(url: &str) -> Result<String, Error> {
+    let max_bytes = env_number("RECIPE_IMPORT_MAX_BYTES", 2_000_000) as usize;
+    let mut url =
+        reqwest::Url::parse(url).map_err(|_| Error::BadRequest(format!("invalid url {url}")))?;
+
+    // redirects are followed by hand to check every address
+    for _ in 0..=MAX_REDIRECTS {
+        let (host, addr) = resolve_public(&url).await?;
+        let mut client = reqwest::Client::builder()
+            .user_agent(USER_AGENT_VALUE)
+            .redirect(reqwest::redirect::Policy::none());
+        if let Some(host) = host {
+            // connect to the checked address instead of resolving again
+            client = client.resolve(&host, addr);
+        }
+        let client = client.build().map_err(|e| {
+            error!("failed to build import http client: {e}");
+            Error::InternalServer
+        })?;
+
+        let mut response = client.get(url.clone()).send().await.map_err(|e| {
+            warn!("failed to fetch {url}: {e}");
+            Error::BadRequest(format!("failed to fetch {url}"))
+        })?;
+
+        if response.status().is_redirection() {
+            let Some(location) = response
+                .headers()
+                .get(http::header::LOCATION)
+                .and_then(|l| l.to_str().ok())
+            else {
+                return Err(Error::BadRequest(format!("{url} redirects nowhere")));
+            };
+            url = url
+                .join(location)
+                .map_err(|_| Error::BadRequest(format!("invalid redirect to {location}")))?;
+            continue;
+        }
+        if !response.status().is_success() {
+            return Err(Error::BadRequest(format!(
+                "{url} responded with {}",
+                response.status()
+            )));
+        }
+        if response.content_length().unwrap_or(0) as usize > max_bytes {
+            return Err(Error::PayloadTooLarge);
+        }
+
+        let mut body: Vec<u8> = vec![];
+        while let Some(chunk) = response.chunk().await.map_err(|e| {
+            warn!("failed to read {url}: {e}");
+            Error::BadRequest(format!("failed to read {url}"))
+        })? {
+            body.extend_from_slice(&chunk);
+            if body.len() > max_bytes {
+                return Err(Error::PayloadTooLarge);
+            }
+        }
+
+        info!("📥 fetched {} bytes from {url}", body.len());
+        return Ok(String::from_utf8_lossy(&body).into_owned());
+    }
+
+    Err(Error::BadRequest(format!("too many redirects from {url}")))
+}
+
+/// Address to connect to, the host is none for ip urls.
+async fn resolve_public(url: &reqwest::Url) -> Result<(Option<String>, SocketAddr), Error> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::BadRequest(
+            "only http and https urls can be imported".to_string(),
+        ));
+    }
+    let Some(host) = url.host_str() else {
+        return Err(Error::BadRequest(format!("{url} has no host")));
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let (host, addrs) = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => (None, vec![SocketAddr::new(ip, port)]),
+        Err(_) => {
+            let addrs = tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|_| Error::BadRequest(format!("can't resolve {host}")))?
+                .collect::<Vec<_>>();
+            (Some(host.to_string()), addrs)
+        }
+    };
+
+    // all addresses are checked, the client may pick any of them
+    match addrs.first() {
+        Some(addr) if addrs.iter().all(|a| is_public(a.ip())) => Ok((host, *addr)),
+        Some(_) => {
+            warn!("🚫 blocked import from {url}, it resolves to {addrs:?}");
+            Err(Error::Forbidden(
+                "recipes can only be imported from public addresses".to_string(),
+            ))
+        }
+        None => Err(Error::BadRequest(format!("can't resolve {url}"))),
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (b & 0xc0) == 64) // shared address space of carrier-grade nat
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            let first = segments[0];
+            // ipv4 mapped, ipv4 compatible, nat64 and 6to4 addresses reach the embedded ipv4
+            let embedded = match segments {
+                [0, 0, 0, 0, 0, 0xffff, ..] | [0x64, 0xff9b, 0, 0, 0, 0, ..] => {
+                    Some(v4(segments[6], segments[7]))
+                }
+                [0, 0, 0, 0, 0, 0, high, low] if high != 0 || low > 1 => Some(v4(high, low)),
+                [0x2002, high, low, ..] => Some(v4(high, low)),
+                _ => None,
+            };
+            match embedded {
+                Some(ip) => is_public(IpAddr::V4(ip)),
+                None => {
+                    !(ip.is_loopback()
+                        || ip.is_unspecified()
+                        || ip.is_multicast()
+                        || (first & 0xfe00) == 0xfc00 // unique local
+                        || (first & 0xffc0) == 0xfe80) // link local
+                }
+            }
+        }
+    }
+}
+
+fn v4(high: u16, low: u16) -> Ipv4Addr {
+    Ipv4Addr::from(((high as u32) << 16) | low as u32)
+}
+
+/// Contents of all `application/ld+json` scripts that parse.
+fn json_ld(html: &str) -> Vec<Value> {
+    JSON_LD
+        .captures_iter(html)
+        .filter_map(|c| match serde_json::from_str(c.get(1)?.as_str().trim()) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                debug!("skipping invalid json-ld: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// The `Recipe` in the JSON-LD, also inside arrays and `@graph`.
+fn find_recipe(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Array(values) => values.iter().find_map(find_recipe),
+        Value::Object(object) => {
+            let is_recipe = match object.get("@type") {
+                Some(Value::String(t)) => t == "Recipe",
+                Some(Value::Array(types)) => types.iter().any(|t| t == "Recipe"),
+                _ => false,
+            };
+            if is_recipe {
+                Some(value)
+            } else {
+                object.get("@graph").and_then(find_recipe)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    let values = match value {
+        Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+        Some(Value::String(value)) => value.lines().collect(),
+        _ => vec![],
+    };
+    values
+        .into_iter()
+        .map(clean)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Steps of `HowToStep`s, `HowToSection`s or plain text.
+fn collect_instructions(value: &Value, instructions: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            instructions.extend(text.lines().map(clean).filter(|s| !s.is_empty()))
+        }
+        Value::Array(values) => {
+            for value in values {
+                collect_instructions(value, instructions);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(steps) = object.get("itemListElement") {
+                if let Some(name) = object.get("name").and_then(Value::as_str) {
+                    instructions.push(clean(name));
+                }
+                collect_instructions(steps, instructions);
+            } else if let Some(text) = object.get("text") {
+                collect_instructions(text, instructions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Servings from `recipeYield`, e.g. `4`, `"4 Portionen"` or `["4", "4 Portionen"]`.
+fn servings(value: &Value) -> Option<u32> {
+    match value {
+        Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        Value::String(s) => s
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|s| !s.is_empty())
+            .and_then(|s| s.parse().ok()),
+        Value::Array(values) => values.iter().find_map(servings),
+        _ => None,
+    }
+    .filter(|s| *s > 0)
+}
+
+/// Plain text without tags, entities decoded and whitespace collapsed.
+fn clean(text: &str) -> String {
+    let without_tags = TAG.replace_all(text, " ");
+    let decoded = html_escape::decode_html_entities(&without_tags);
+
+    decoded
+        .replace('\u{ad}', "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn public(ip: &str) -> bool {
+        is_public(ip.parse().expect("invalid ip"))
+    }
+
+    #[test]
+    fn blocks_private_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fc00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+            "2002:c0a8:0101::1",
+            "2002:7f00:1::",
+        ] {
+            assert!(!public(ip), "{ip} should be blocked");
+        }
+    }
+
+    #[test]
+    fn allows_public_addresses() {
+        for ip in [
+            "93.184.215.14",
+            "2606:2800:21f:cb07:6820:80da:af6b:8b2c",
+            "::ffff:93.184.215.14",
+            "64:ff9b::5db8:d70e",
+            "2002:5db8:d70e::1",
+        ] {
+            assert!(public(ip), "{ip} should be allowed");
+        }
+    }
+
+    #[test]
+    fn reads_servings() {
+        assert_eq!(servings(&json!(4)), Some(4));
+        assert_eq!(servings(&json!("4 Portionen")), Some(4));
+        assert_eq!(servings(&json!(["", "2 Stück"])), Some(2));
+        assert_eq!(servings(&json!("0")), None);
+        assert_eq!(servings(&json!("einige")), None);
+    }
+
+    #[test]
+    fn finds_recipe_in_graph_and_type_arrays() {
+        let graph = json!({
+            "@context": "https://schema.org",
+            "@graph": [
+                { "@type": "WebPage", "name": "Seite" },
+                { "@type": ["Recipe", "NewsArticle"], "name": "Suppe" },
+            ],
+        });
+        let recipe = find_recipe(&graph).expect("no recipe in graph");
+        assert_eq!(recipe["name"], "Suppe");
+
+        let list = json!([{ "@type": "Organization" }, { "@type": "Recipe", "name": "Kuchen" }]);
+        assert_eq!(
+            find_recipe(&list).map(|r| &r["name"]),
+            Some(&json!("Kuchen"))
+        );
+        assert!(find_recipe(&json!({ "@type": "Article" })).is_none());
+    }
+
+    #[test]
+    fn collects_steps_of_sections() {
+        let value = json!([
+            {
+                "@type": "HowToSection",
+                "name": "Teig",
+                "itemListElement": [
+                    { "@type": "HowToStep", "text": "Mehl <b>sieben</b>." },
+                    { "@type": "HowToStep", "text": "Eier&nbsp;unterr&uuml;hren &amp; kneten." },
+                ],
+            },
+            { "@type": "HowToStep", "text": "Backen." },
+        ]);
+        let mut instructions = vec![];
+        collect_instructions(&value, &mut instructions);
+        assert_eq!(
+            instructions,
+            vec![
+                "Teig",
+                "Mehl sieben .",
+                "Eier unterrühren & kneten.",
+                "Backen."
+            ]
+        );
+    }
+
+    #[test]
+    fn cleans_text() {
+        assert_eq!(
+            clean("  Zwiebel&shy;würfel <i>fein</i>\n"),
+            "Zwiebelwürfel fein"
+        );
+        assert_eq!(clean("&#228;&#xE4; &lt;3 &quot;x&quot;"), "ää <3 \"x\"");
+        assert_eq!(
+            clean("Gr&ouml;&szlig;e &Auml;pfel &ndash; 200&nbsp;g"),
+            "Größe Äpfel – 200 g"
+        );
+    }
+
+    #[test]
+    fn reads_recipe_from_html() {
+        let html = r#"<html><head>
+            <script type="application/ld+json">{ invalid</script>
+            <script type="application/ld+json">
+                {"@graph": [{"@type": "Recipe", "name": "Suppe", "recipeYield": "4 Portionen",
+                  "recipeIngredient": ["1 Zwiebel", " "], "recipeInstructions": "Kochen."}]}
+            </script></head></html>"#;
+        let recipe = ImportedRecipe::from_html(html, None).expect("no recipe");
+        assert_eq!(recipe.title.as_deref(), Some("Suppe"));
+        assert_eq!(recipe.servings, Some(4));
+        assert_eq!(recipe.ingredients, vec!["1 Zwiebel"]);
+        assert_eq!(recipe.text(), "1 Zwiebel\n\nKochen.");
+    }
+}
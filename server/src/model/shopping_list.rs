@@ -0,0 +1,591 @@
+use crate::prelude::*;
+use surrealdb::sql::Datetime;
+
+const ENTRY_FIELDS: &str = "id, list, position, ingredient, name, quantity, unit, note, item as item_id, item.* as item, item_quantity, checked";
+
+/// Persisted shopping list of a user, totals as in `Item::price_total`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShoppingList {
+    pub id: String,
+    pub title: Option<String>,
+    pub recipes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub entries: Vec<ShoppingListEntry>,
+    pub price_total: f32,
+    pub price_total_string: String,
+    pub open_price_total: f32, // of the entries not checked yet
+    pub open_price_total_string: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShoppingListEntry {
+    pub id: String,
+    pub ingredient: Option<String>, // key of the canonical ingredient, none for free-text extras
+    pub name: String,
+    pub quantity: Option<f64>,
+    pub unit: Option<Unit>,
+    pub note: Option<String>,
+    pub item: Option<Item>,
+    pub item_quantity: i64,
+    pub checked: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct NewShoppingList {
+    pub title: Option<String>,
+    pub recipe_id: Option<String>,
+    pub meal_plan_id: Option<String>,
+    pub include_at_home: bool, // ingredients probably at home are left out otherwise
+}
+
+/// Free-text extra, e.g. `Spülmittel`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NewShoppingListEntry {
+    pub name: String,
+    #[serde(default)]
+    pub quantity: Option<f64>,
+    #[serde(default)]
+    pub unit: Option<Unit>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct ShoppingListEntryUpdate {
+    pub name: Option<String>,
+    pub quantity: Option<f64>,
+    pub unit: Option<Unit>,
+    pub note: Option<String>,
+    pub item_quantity: Option<i64>,
+    pub checked: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ShoppingListDb {
+    user: Thing,
+    title: Option<String>,
+    recipes: Vec<Thing>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ShoppingListEntryDb {
+    list: Thing,
+    position: i64,
+    ingredient: Option<Thing>,
+    name: String,
+    quantity: Option<f64>,
+    unit: Option<Unit>,
+    note: Option<String>,
+    item: Option<Thing>,
+    item_quantity: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ShoppingListRow {
+    id: Thing,
+    title: Option<String>,
+    recipes: Vec<Thing>,
+    created_at: Datetime,
+    archived_at: Option<Datetime>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EntryRow {
+    id: Thing,
+    list: Thing,
+    ingredient: Option<Thing>,
+    name: String,
+    quantity: Option<f64>,
+    unit: Option<Unit>,
+    note: Option<String>,
+    item_id: Option<Thing>,
+    item: Option<ItemDb>,
+    item_quantity: i64,
+    checked: bool,
+}
+
+impl From<EntryRow> for ShoppingListEntry {
+    fn from(row: EntryRow) -> Self {
+        let item = match (row.item_id, row.item) {
+            (Some(id), Some(item)) => Some(item.into_item(id.id.to_raw())),
+            _ => None,
+        };
+        Self {
+            id: row.id.id.to_raw(),
+            ingredient: row.ingredient.map(|i| i.id.to_raw()),
+            name: row.name,
+            quantity: row.quantity,
+            unit: row.unit,
+            note: row.note,
+            item,
+            item_quantity: row.item_quantity,
+            checked: row.checked,
+        }
+    }
+}
+
+impl ShoppingList {
+    fn new(row: ShoppingListRow, entries: Vec<ShoppingListEntry>) -> Self {
+        let total = |entries: &mut dyn Iterator<Item = &ShoppingListEntry>| -> f32 {
+            entries
+                .filter_map(|e| {
+                    e.item
+                        .as_ref()
+                        .map(|i| i.price_total(e.item_quantity.max(0) as usize))
+                })
+                .sum()
+        };
+        let price_total = total(&mut entries.iter());
+        let open_price_total = total(&mut entries.iter().filter(|e| !e.checked));
+
+        Self {
+            id: row.id.id.to_raw(),
+            title: row.title,
+            recipes: row.recipes.into_iter().map(|r| r.id.to_raw()).collect(),
+            created_at: row.created_at.0,
+            archived_at: row.archived_at.map(|a| a.0),
+            entries,
+            price_total,
+            price_total_string: format!("{:.2}", price_total),
+            open_price_total,
+            open_price_total_string: format!("{:.2}", open_price_total),
+        }
+    }
+
+    /// Lists of the user, latest first. Archived lists only if asked for.
+    pub async fn list(
+        db: &Surreal<Any>,
+        username: &str,
+        archived: bool,
+    ) -> Result<Vec<ShoppingList>, Error> {
+        let rows: Vec<ShoppingListRow> = db
+            .query(
+                r#"
+                    select id, title, recipes, created_at, archived_at
+                    from shopping_list
+                    where
+                        user = type::thing('user', $username)
+                        and (archived_at != none) = $archived
+                    order by created_at desc
+                "#,
+            )
+            .bind(("username", username.to_string()))
+            .bind(("archived", archived))
+            .await?
+            .take(0)?;
+
+        let ids = rows.iter().map(|r| r.id.clone()).collect();
+        let mut entries = Self::entries(db, ids).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let entries = entries.remove(&row.id.id.to_raw()).unwrap_or_default();
+                Self::new(row, entries)
+            })
+            .collect())
+    }
+
+    pub async fn get(db: &Surreal<Any>, username: &str, id: &str) -> Result<ShoppingList, Error> {
+        let row = Self::row(db, username, id).await?;
+        let entries = Self::entries(db, vec![row.id.clone()])
+            .await?
+            .remove(&row.id.id.to_raw())
+            .unwrap_or_default();
+        Ok(Self::new(row, entries))
+    }
+
+    /// Creates a list, filled with the ingredients of the recipe or meal plan and the items matched
+    /// for them.
+    pub async fn create(
+        db: &Surreal<Any>,
+        username: &str,
+        new: NewShoppingList,
+    ) -> Result<ShoppingList, Error> {
+        // ingredients of the recipe or the consolidated ones of the meal plan
+        let (title, recipes, mut ingredients) = match (&new.recipe_id, &new.meal_plan_id) {
+            (Some(_), Some(_)) => {
+                return Err(Error::BadRequest(
+                    "either recipe_id or meal_plan_id is allowed".to_string(),
+                ))
+            }
+            (Some(recipe_id), None) => {
+                let recipe = RecipeHistory::get(db, username, recipe_id).await?;
+                (
+                    recipe.summary.title,
+                    vec![recipe.summary.id],
+                    recipe.ingredients,
+                )
+            }
+            (None, Some(meal_plan_id)) => {
+                let plan = MealPlan::get(db, username, meal_plan_id).await?;
+                let ingredients = plan.ingredients(db, username).await?;
+                (plan.title.clone(), plan.recipe_ids(), ingredients)
+            }
+            (None, None) => (None, vec![], vec![]),
+        };
+
+        // the list holds what is left to buy after the pantry
+        Pantry::subtract(db, username, &mut ingredients).await?;
+        ingredients.retain(|i| new.include_at_home || !i.probably_at_home);
+        for ingredient in ingredients.iter_mut() {
+            ingredient.item_quantity = ingredient.packages_required(db).await?;
+        }
+
+        let title = new
+            .title
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .or(title);
+        let list_db = ShoppingListDb {
+            user: Thing::from(("user", username)),
+            title,
+            recipes: recipes
+                .iter()
+                .map(|id| Thing::from(("recipe", id.as_str())))
+                .collect(),
+        };
+        let Some(row): Option<ShoppingListRow> = db
+            .query("create shopping_list content $list")
+            .bind(("list", list_db))
+            .await?
+            .take(0)?
+        else {
+            error!("failed to store shopping list");
+            return Err(Error::InternalServer);
+        };
+
+        Self::insert_ingredients(db, &row.id, ingredients).await?;
+
+        let list = Self::get(db, username, &row.id.id.to_raw()).await?;
+        info!(
+            "📝 {username} created shopping list {} with {} entries",
+            list.id,
+            list.entries.len()
+        );
+        Ok(list)
+    }
+
+    pub async fn add_entry(
+        db: &Surreal<Any>,
+        username: &str,
+        id: &str,
+        entry: NewShoppingListEntry,
+    ) -> Result<ShoppingList, Error> {
+        let list = Self::editable(db, username, id).await?;
+
+        let name = entry.name.trim().to_string();
+        if name.is_empty() {
+            return Err(Error::BadRequest("name is required".to_string()));
+        }
+        let entry_db = ShoppingListEntryDb {
+            list: list.id.clone(),
+            position: Self::next_position(db, &list.id).await?,
+            ingredient: None,
+            name,
+            quantity: entry.quantity.filter(|q| *q > 0.0),
+            unit: entry.unit,
+            note: entry.note.filter(|n| !n.trim().is_empty()),
+            item: None,
+            item_quantity: 0,
+        };
+        db.query("create shopping_list_entry content $entry")
+            .bind(("entry", entry_db))
+            .await?
+            .check()?;
+
+        Self::get(db, username, id).await
+    }
+
+    /// Updates the entry, `selected` replaces the item, e.g. with one of the `alternatives`.
+    /// Selected are ids, see `selectable_item`, with the search term the item was found with.
+    pub async fn update_entry(
+        state: &AppState,
+        username: &str,
+        id: &str,
+        entry_id: &str,
+        update: ShoppingListEntryUpdate,
+        selected: Option<(String, Option<String>, &Vendor)>,
+    ) -> Result<ShoppingList, Error> {
+        let db = &state.db;
+        let list = Self::editable(db, username, id).await?;
+        let entry = Self::entry(db, &list.id, entry_id).await?;
+
+        let mut query = vec![];
+        let name = update.name.map(|n| n.trim().to_string());
+        if let Some(name) = &name {
+            if name.is_empty() {
+                return Err(Error::BadRequest("name must not be empty".to_string()));
+            }
+            query.push("name = $name");
+        }
+        if let Some(quantity) = update.quantity {
+            if quantity <= 0.0 {
+                return Err(Error::BadRequest("quantity must be positive".to_string()));
+            }
+            query.push("quantity = $quantity");
+        }
+        if update.unit.is_some() {
+            query.push("unit = $unit");
+        }
+        // empty notes clear the field
+        let note = update.note.map(|n| n.trim().to_string());
+        if let Some(note) = &note {
+            query.push(if note.is_empty() {
+                "note = none"
+            } else {
+                "note = $note"
+            });
+        }
+        if let Some(item_quantity) = update.item_quantity {
+            if item_quantity < 0 {
+                return Err(Error::BadRequest(
+                    "item_quantity must not be negative".to_string(),
+                ));
+            }
+            query.push("item_quantity = $item_quantity");
+        }
+        if update.checked.is_some() {
+            query.push("checked = $checked");
+        }
+
+        let mut item = None;
+        if let Some((item_id, search, vendor)) = selected {
+            let selected =
+                Self::selectable_item(state, username, &entry, &item_id, search, vendor).await?;
+
+            // the selection is remembered as match of the ingredient for the user
+            let item_id = match &entry.ingredient {
+                Some(ingredient) => {
+                    store_item_match(
+                        db,
+                        &selected,
+                        vendor,
+                        &ingredient.id.to_raw(),
+                        Some(username),
+                    )
+                    .await?;
+                    selected.id.replace("-", "")
+                }
+                None => store_item(db, &selected, vendor).await?,
+            };
+            item = Some(Thing::from(("item", item_id.as_str())));
+            query.push("item = $item");
+            if update.item_quantity.is_none() && entry.item_quantity == 0 {
+                query.push("item_quantity = 1");
+            }
+        }
+
+        if !query.is_empty() {
+            db.query(format!("update $entry set {}", query.join(", ")))
+                .bind(("entry", entry.id))
+                .bind(("name", name))
+                .bind(("quantity", update.quantity))
+                .bind(("unit", update.unit))
+                .bind(("note", note))
+                .bind(("item_quantity", update.item_quantity))
+                .bind(("checked", update.checked))
+                .bind(("item", item))
+                .await?
+                .check()?;
+        }
+
+        Self::get(db, username, id).await
+    }
+
+    /// Item the user may select for the entry, one matched for the user before or one the vendor
+    /// finds for `search`. Item content sent by clients is never stored, others rely on it.
+    async fn selectable_item(
+        state: &AppState,
+        username: &str,
+        entry: &EntryRow,
+        item_id: &str,
+        search: Option<String>,
+        vendor: &Vendor,
+    ) -> Result<Item, Error> {
+        let key = item_id.replace("-", "");
+        let mut matched: Vec<ItemDb> = state
+            .db
+            .query("select value in.* from matches where in = type::thing('item', $key) and user = type::thing('user', $username) and in.vendor = $vendor limit 1")
+            .bind(("key", key.clone()))
+            .bind(("username", username.to_string()))
+            .bind(("vendor", vendor.name()))
+            .await?
+            .take(0)?;
+        if let Some(item) = matched.pop() {
+            return Ok(item.into_item(key));
+        }
+
+        let mut ingredient = Ingredient::new(
+            search
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| entry.name.clone()),
+            entry.quantity.unwrap_or(1.0),
+            entry.unit.unwrap_or(Unit::Piece),
+        );
+        vendor
+            .find_items(state, &mut ingredient, &SearchOptions::default())
+            .await?;
+
+        ingredient
+            .alternatives
+            .into_iter()
+            .find(|i| i.id.replace("-", "") == key)
+            .ok_or_else(|| {
+                Error::BadRequest(format!(
+                    "item {item_id} is not found for '{}' at {}",
+                    ingredient.name,
+                    vendor.name()
+                ))
+            })
+    }
+
+    pub async fn remove_entry(
+        db: &Surreal<Any>,
+        username: &str,
+        id: &str,
+        entry_id: &str,
+    ) -> Result<ShoppingList, Error> {
+        let list = Self::editable(db, username, id).await?;
+        let entry = Self::entry(db, &list.id, entry_id).await?;
+
+        db.query("delete $entry")
+            .bind(("entry", entry.id))
+            .await?
+            .check()?;
+
+        Self::get(db, username, id).await
+    }
+
+    /// Archived lists are kept for the history but can't be changed anymore.
+    pub async fn archive(
+        db: &Surreal<Any>,
+        username: &str,
+        id: &str,
+    ) -> Result<ShoppingList, Error> {
+        let list = Self::editable(db, username, id).await?;
+
+        db.query("update $list set archived_at = time::now()")
+            .bind(("list", list.id))
+            .await?
+            .check()?;
+
+        Self::get(db, username, id).await
+    }
+
+    async fn insert_ingredients(
+        db: &Surreal<Any>,
+        list: &Thing,
+        ingredients: Vec<Ingredient>,
+    ) -> Result<(), Error> {
+        let mut entries = vec![];
+        for (i, ingredient) in ingredients.into_iter().enumerate() {
+            let item = ingredient.item();
+            let item_quantity = match &item {
+                Some(_) => ingredient.item_quantity.max(1),
+                None => 0,
+            };
+            entries.push(ShoppingListEntryDb {
+                list: list.clone(),
+                position: i as i64,
+                ingredient: Some(Thing::from(("ingredient", ingredient.key().as_str()))),
+                quantity: Some(ingredient.quantity_to_buy()).filter(|q| *q > 0.0),
+                name: ingredient.name,
+                unit: Some(ingredient.unit),
+                note: None,
+                item: item.map(|i| Thing::from(("item", i.id.as_str()))),
+                item_quantity,
+            });
+        }
+
+        if !entries.is_empty() {
+            db.query("insert into shopping_list_entry $entries")
+                .bind(("entries", entries))
+                .await?
+                .check()?;
+        }
+        Ok(())
+    }
+
+    /// Entries of the lists in their order, keyed by the id of the list.
+    async fn entries(
+        db: &Surreal<Any>,
+        lists: Vec<Thing>,
+    ) -> Result<HashMap<String, Vec<ShoppingListEntry>>, Error> {
+        let rows: Vec<EntryRow> = db
+            .query(
+                format!("select {ENTRY_FIELDS} from shopping_list_entry where list in $lists order by position"),
+            )
+            .bind(("lists", lists))
+            .await?
+            .take(0)?;
+
+        let mut entries: HashMap<String, Vec<ShoppingListEntry>> = HashMap::new();
+        for row in rows {
+            entries
+                .entry(row.list.id.to_raw())
+                .or_default()
+                .push(row.into());
+        }
+        Ok(entries)
+    }
+
+    async fn next_position(db: &Surreal<Any>, list: &Thing) -> Result<i64, Error> {
+        let position: Option<i64> = db
+            .query("math::max(select value position from shopping_list_entry where list = $list)")
+            .bind(("list", list.clone()))
+            .await?
+            .take(0)?;
+        Ok(position.map(|p| p + 1).unwrap_or(0))
+    }
+
+    async fn entry(db: &Surreal<Any>, list: &Thing, entry_id: &str) -> Result<EntryRow, Error> {
+        let mut rows: Vec<EntryRow> = db
+            .query(
+                format!("select {ENTRY_FIELDS} from shopping_list_entry where id = type::thing('shopping_list_entry', $entry_id) and list = $list"),
+            )
+            .bind(("entry_id", entry_id.to_string()))
+            .bind(("list", list.clone()))
+            .await?
+            .take(0)?;
+
+        rows.pop().ok_or(Error::NotFound)
+    }
+
+    /// The list if the user owns it and it is not archived.
+    async fn editable(
+        db: &Surreal<Any>,
+        username: &str,
+        id: &str,
+    ) -> Result<ShoppingListRow, Error> {
+        let row = Self::row(db, username, id).await?;
+        if row.archived_at.is_some() {
+            return Err(Error::Conflict("shopping list is archived".to_string()));
+        }
+        Ok(row)
+    }
+
+    /// The list if the user owns it.
+    async fn row(db: &Surreal<Any>, username: &str, id: &str) -> Result<ShoppingListRow, Error> {
+        let mut rows: Vec<ShoppingListRow> = db
+            .query(
+                r#"
+                    select id, title, recipes, created_at, archived_at
+                    from shopping_list
+                    where
+                        id = type::thing('shopping_list', $id)
+                        and user = type::thing('user', $username)
+                "#,
+            )
+            .bind(("username", username.to_string()))
+            .bind(("id", id.to_string()))
+            .await?
+            .take(0)?;
+
+        rows.pop().ok_or(Error::NotFound)
+    }
+}
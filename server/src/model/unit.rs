@@ -0,0 +1,184 @@
+use crate::prelude::*;
+use std::str::FromStr;
+
+/// Unit of an ingredient quantity. Serialized as its abbreviation, deserialized from
+/// common spellings and abbreviations regardless of case, e.g. `Gramm`, `gr` or `g`.
+/// Unknown units are deserialized as `Piece`, one odd unit must not fail a whole recipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum Unit {
+    Milligram,
+    Gram,
+    Kilogram,
+    Milliliter,
+    Centiliter,
+    Liter,
+    Tablespoon, // EL
+    Teaspoon,   // TL
+    Pinch,      // Prise
+    Bunch,      // Bund
+    Can,        // Dose
+    Piece,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Mass,
+    Volume,
+    Count,
+}
+
+const SPELLINGS: &[(Unit, &[&str])] = &[
+    (Unit::Milligram, &["mg", "milligramm", "milligram"]),
+    (Unit::Gram, &["g", "gr", "gramm", "gram", "grams"]),
+    (Unit::Kilogram, &["kg", "kilogramm", "kilogram", "kilo"]),
+    (Unit::Milliliter, &["ml", "milliliter", "millilitre"]),
+    (Unit::Centiliter, &["cl", "zentiliter", "centiliter"]),
+    (Unit::Liter, &["l", "liter", "litre", "ltr"]),
+    (Unit::Tablespoon, &["el", "esslöffel", "essloeffel", "tbsp"]),
+    (Unit::Teaspoon, &["tl", "teelöffel", "teeloeffel", "tsp"]),
+    (Unit::Pinch, &["prise", "prisen", "msp", "messerspitze"]),
+    (Unit::Bunch, &["bund", "bündel", "bd"]),
+    (Unit::Can, &["dose", "dosen", "ds"]),
+    (
+        Unit::Piece,
+        &[
+            "stück",
+            "stueck",
+            "stk",
+            "st",
+            "piece",
+            "pieces",
+            "zehe",
+            "zehen",
+            "scheibe",
+            "scheiben",
+            "packung",
+            "packungen",
+            "pck",
+            "pkg",
+        ],
+    ),
+];
+
+impl Unit {
+    /// Abbreviation as used in German recipes.
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            Unit::Milligram => "mg",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Milliliter => "ml",
+            Unit::Centiliter => "cl",
+            Unit::Liter => "l",
+            Unit::Tablespoon => "EL",
+            Unit::Teaspoon => "TL",
+            Unit::Pinch => "Prise",
+            Unit::Bunch => "Bund",
+            Unit::Can => "Dose",
+            Unit::Piece => "Stück",
+        }
+    }
+
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Unit::Milligram | Unit::Gram | Unit::Kilogram => Dimension::Mass,
+            Unit::Milliliter
+            | Unit::Centiliter
+            | Unit::Liter
+            | Unit::Tablespoon
+            | Unit::Teaspoon => Dimension::Volume,
+            Unit::Pinch | Unit::Bunch | Unit::Can | Unit::Piece => Dimension::Count,
+        }
+    }
+
+    /// Unit quantities of the same dimension are stored in, `g` for mass and `ml` for volume.
+    /// Count units are their own canonical unit, since a `Bund` can't be converted to `Stück`.
+    pub fn canonical(&self) -> Unit {
+        match self.dimension() {
+            Dimension::Mass => Unit::Gram,
+            Dimension::Volume => Unit::Milliliter,
+            Dimension::Count => *self,
+        }
+    }
+
+    /// Factor to the canonical unit, e.g. 1000 for `kg` and 15 for `EL`.
+    fn factor(&self) -> f64 {
+        match self {
+            Unit::Milligram => 0.001,
+            Unit::Kilogram | Unit::Liter => 1000.0,
+            Unit::Centiliter => 10.0,
+            Unit::Tablespoon => 15.0,
+            Unit::Teaspoon => 5.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Converts the quantity to `to`, `None` if the units are of different dimensions.
+    pub fn convert(&self, quantity: f64, to: Unit) -> Option<f64> {
+        if self.canonical() != to.canonical() {
+            return None;
+        }
+        Some(quantity * self.factor() / to.factor())
+    }
+
+    /// Quantity in the canonical unit, e.g. `(1000.0, Unit::Gram)` for 1 kg.
+    pub fn to_canonical(&self, quantity: f64) -> (f64, Unit) {
+        (quantity * self.factor(), self.canonical())
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().trim_end_matches('.').to_lowercase();
+        SPELLINGS
+            .iter()
+            .find(|(_, spellings)| spellings.contains(&s.as_str()))
+            .map(|(unit, _)| *unit)
+            .ok_or_else(|| format!("unknown unit {s}"))
+    }
+}
+
+impl From<String> for Unit {
+    fn from(s: String) -> Self {
+        s.parse().unwrap_or_else(|err| {
+            warn!("{err}, using {}", Unit::Piece);
+            Unit::Piece
+        })
+    }
+}
+
+impl From<Unit> for String {
+    fn from(unit: Unit) -> Self {
+        unit.abbreviation().to_string()
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.abbreviation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_units_are_pieces() {
+        let units: Vec<Unit> =
+            serde_json::from_str(r#"["Gramm", "EL.", "Zehe", "Handvoll", ""]"#).unwrap();
+        assert_eq!(
+            units,
+            vec![
+                Unit::Gram,
+                Unit::Tablespoon,
+                Unit::Piece,
+                Unit::Piece,
+                Unit::Piece
+            ]
+        );
+    }
+}
@@ -1,10 +1,34 @@
 pub use crate::{
     ai::{application_daily_cost, limits, user_daily_cost, user_total_cost, Ai, AiUsage},
-    db::{IngredientDb, ItemDb, Recipe, Relation, Requires, Seeks, User},
-    error::Error,
-    handler::auth::AuthenticatedUser,
-    model::{cash_flow::CashFlow, ingredient::Ingredient, item::Item, vendor::Vendor},
-    util::new_id,
+    db::{
+        store_ingredient, store_item, store_item_match, IngredientDb, ItemDb, Matches, OfferDb,
+        Recipe, Relation, Requires, Seeks, User,
+    },
+    error::{Error, VendorError},
+    handler::auth::{AdminUser, AuthenticatedUser},
+    model::{
+        aisle::{Aisle, AisleGroup, AisleKeywords},
+        cash_flow::CashFlow,
+        conversion::{Density, MissingDensity, PackageAmount},
+        diet::{Diet, DietConflict, DietaryProfile},
+        export::{Export, ExportFormat, ExportLine},
+        ingredient::Ingredient,
+        ingredient_mapping::IngredientMapping,
+        item::{Item, Offer},
+        item_details::{ItemDetails, Nutrition},
+        meal_plan::{Meal, MealPlan, MealPlanIn},
+        nutrition::{NutrientEstimate, NutritionEstimate},
+        pantry::{Pantry, PantryItem},
+        recipe::{RecipeDetail, RecipeHistory, RecipeSummary, RecipeUpdate},
+        recipe_import::ImportedRecipe,
+        shopping_list::{
+            NewShoppingList, NewShoppingListEntry, ShoppingList, ShoppingListEntry,
+            ShoppingListEntryUpdate,
+        },
+        unit::{Dimension, Unit},
+        vendor::{SearchOptions, SearchQuery, Vendor, VendorHttp, VendorItems},
+    },
+    util::{env_number, new_id},
     AppState,
 };
 
@@ -14,7 +38,7 @@ pub use axum::{
     body::Body,
     extract::{Extension, Json, Path, Query},
     http::{self, request::Parts, StatusCode},
-    routing::{delete, get, patch, post},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 pub use axum_extra::extract::cookie::{Cookie, CookieJar};
@@ -22,7 +46,7 @@ pub use bcrypt::{hash, verify, DEFAULT_COST};
 pub use chrono::{DateTime, Duration, Utc};
 pub use dotenv::dotenv;
 pub use http::{
-    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, COOKIE, USER_AGENT},
+    header::{ACCEPT, AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE, COOKIE, USER_AGENT},
     Method, Request,
 };
 pub use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
@@ -7,3 +7,11 @@ pub fn new_id() -> String {
         .map(char::from)
         .collect()
 }
+
+/// Number from the environment, the default if it is missing or no number.
+pub fn env_number(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(default)
+}